@@ -0,0 +1,67 @@
+use {
+    crate::{
+        common::{
+            logger::Logger,
+            lookup_table::{
+                create_wallet_lookup_table, extend_wallet_lookup_table, get_lookup_table, import_lookup_table,
+            },
+            utils::AppState,
+        },
+        dex::pump_fun::pump_lookup_table_addresses,
+    },
+    anyhow::{anyhow, Result},
+    solana_sdk::pubkey::Pubkey,
+    std::str::FromStr,
+};
+
+const USAGE: &str = "Usage: alt <create | extend [LOOKUP_TABLE] | show [LOOKUP_TABLE]>";
+
+pub async fn run(args: &[String], state: &AppState) -> Result<()> {
+    let logger = Logger::new("[ALT]".to_string());
+    let client = &state.rpc_nonblocking_client;
+
+    match args.first().map(String::as_str) {
+        Some("create") => {
+            let (lookup_table, signature) = create_wallet_lookup_table(client, &state.wallet).await?;
+            logger.success(format!("Created lookup table {} in tx {}", lookup_table, signature));
+
+            if let Some(signature) =
+                extend_wallet_lookup_table(client, &state.wallet, &lookup_table, &pump_lookup_table_addresses()?).await?
+            {
+                logger.success(format!("Added Pump accounts to {} in tx {}", lookup_table, signature));
+            }
+            logger.info(format!("Set LOOKUP_TABLE={} to build v0 swaps with it", lookup_table));
+            Ok(())
+        }
+        Some("extend") => {
+            let lookup_table = lookup_table_arg(args.get(1))?;
+            match extend_wallet_lookup_table(client, &state.wallet, &lookup_table, &pump_lookup_table_addresses()?).await? {
+                Some(signature) => logger.success(format!("Extended {} in tx {}", lookup_table, signature)),
+                None => logger.info(format!("{} already holds every Pump account", lookup_table)),
+            }
+            Ok(())
+        }
+        Some("show") => {
+            let lookup_table = lookup_table_arg(args.get(1))?;
+            let table = get_lookup_table(client, &lookup_table).await?;
+            let expected = pump_lookup_table_addresses()?;
+
+            logger.info(format!("[LOOKUP TABLE] => {} ({} addresses)", table.key, table.addresses.len()));
+            for address in &table.addresses {
+                logger.info(format!("   * {}", address));
+            }
+            for address in expected.iter().filter(|address| !table.addresses.contains(address)) {
+                logger.warning(format!("   * [MISSING] => {}", address));
+            }
+            Ok(())
+        }
+        _ => Err(anyhow!(USAGE)),
+    }
+}
+
+fn lookup_table_arg(arg: Option<&String>) -> Result<Pubkey> {
+    match arg {
+        Some(lookup_table) => Ok(Pubkey::from_str(lookup_table)?),
+        None => import_lookup_table()?.ok_or_else(|| anyhow!("LOOKUP_TABLE not set. {}", USAGE)),
+    }
+}
@@ -0,0 +1,82 @@
+use {
+    crate::{
+        commands::pnl::log_group,
+        common::logger::Logger,
+        engine::backtest::{load_trades, run_backtest, BacktestConfig},
+    },
+    anyhow::{anyhow, Result},
+    std::collections::BTreeMap,
+};
+
+const USAGE: &str = "Usage: backtest [--target WALLET] [--delay-slots N] [--delay-ms N] [--balance SOL] \
+                     [--equity OUTPUT] [--csv OUTPUT] INPUT";
+
+// Replays a journal or fixture file offline; no RPC is used.
+pub async fn run(args: &[String]) -> Result<()> {
+    let logger = Logger::new("[BACKTEST]".to_string());
+
+    let mut config = BacktestConfig::from_env()?;
+    let mut input = None;
+    let mut equity_path = None;
+    let mut csv_path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| anyhow!(USAGE));
+        match arg.as_str() {
+            "--target" => config.target = Some(value()?),
+            "--delay-slots" => config.latency.delay_slots = value()?.parse()?,
+            "--delay-ms" => config.latency.delay_ms = value()?.parse()?,
+            "--balance" => config.balance = (value()?.parse::<f64>()? * 1_000_000_000.0) as u64,
+            "--equity" => equity_path = Some(value()?),
+            "--csv" => csv_path = Some(value()?),
+            arg if arg.starts_with("--") => return Err(anyhow!(USAGE)),
+            arg => input = Some(arg.to_string()),
+        }
+    }
+    let input = input.ok_or_else(|| anyhow!(USAGE))?;
+
+    if config.filters.needs_rpc() {
+        logger.warning("Name, symbol and token age filters need RPC and are not applied".to_string());
+    }
+    let trades = load_trades(&input)?;
+    let result = run_backtest(&trades, &config)?;
+    logger.info(format!(
+        "{} trades from {}, {} signals, delay {} slots / {}ms, tip {} SOL",
+        result.trades,
+        input,
+        result.signals,
+        config.latency.delay_slots,
+        config.latency.delay_ms,
+        config.tip as f64 / 1_000_000_000.0
+    ));
+
+    let mut skips: BTreeMap<String, u32> = BTreeMap::new();
+    for reason in result.records.iter().filter_map(|record| record.skip_reason.clone()) {
+        *skips.entry(reason).or_default() += 1;
+    }
+    for (reason, count) in &skips {
+        logger.info(format!("[SKIPPED] => {}: {}", reason, count));
+    }
+
+    let report = &result.report;
+    log_group(&logger, "TOTAL", &BTreeMap::from([("all".to_string(), report.total.clone())]));
+    log_group(&logger, "BY MINT", &report.by_mint);
+    logger.success(format!(
+        "[EQUITY] => Start: {} SOL, End: {} SOL, Return: {:.2}%, Max drawdown: {:.2}%, Open positions: {}",
+        result.starting_balance as f64 / 1_000_000_000.0,
+        result.final_equity() as f64 / 1_000_000_000.0,
+        result.return_pct(),
+        result.max_drawdown,
+        report.positions.values().filter(|position| position.token_amount > 0).count()
+    ));
+
+    if let Some(equity_path) = equity_path {
+        std::fs::write(&equity_path, result.equity_csv())?;
+        logger.success(format!("Wrote {}", equity_path));
+    }
+    if let Some(csv_path) = csv_path {
+        std::fs::write(&csv_path, report.to_csv())?;
+        logger.success(format!("Wrote {}", csv_path));
+    }
+    Ok(())
+}
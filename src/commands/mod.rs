@@ -0,0 +1,30 @@
+use {crate::common::utils::AppState, anyhow::Result};
+
+pub mod alt;
+pub mod backtest;
+pub mod nonce;
+pub mod pnl;
+pub mod replay;
+pub mod rpc;
+
+// Returns `None` when the arguments are not a subcommand, in which case the
+// bot starts monitoring as usual.
+pub async fn run(args: &[String], state: &AppState) -> Option<Result<()>> {
+    match args.first().map(String::as_str) {
+        Some("alt") => Some(alt::run(&args[1..], state).await),
+        Some("nonce") => Some(nonce::run(&args[1..], state).await),
+        Some("rpc") => Some(rpc::run(&args[1..], state).await),
+        _ => None,
+    }
+}
+
+// Subcommands that work from local files only, run before any RPC client is
+// built.
+pub async fn run_offline(args: &[String]) -> Option<Result<()>> {
+    match args.first().map(String::as_str) {
+        Some("pnl") => Some(pnl::run(&args[1..]).await),
+        Some("backtest") => Some(backtest::run(&args[1..]).await),
+        Some("replay") => Some(replay::run(&args[1..]).await),
+        _ => None,
+    }
+}
@@ -0,0 +1,49 @@
+use {
+    crate::common::{
+        logger::Logger,
+        nonce::{create_nonce_account, get_nonce_info},
+        utils::AppState,
+    },
+    anyhow::{anyhow, Result},
+    solana_sdk::{pubkey::Pubkey, signature::Signer},
+    std::str::FromStr,
+};
+
+const USAGE: &str = "Usage: nonce <create | show [NONCE_ACCOUNT]>";
+
+pub async fn run(args: &[String], state: &AppState) -> Result<()> {
+    let logger = Logger::new("[NONCE]".to_string());
+
+    match args.first().map(String::as_str) {
+        Some("create") => {
+            let (nonce_account, signature) =
+                create_nonce_account(&state.rpc_nonblocking_client, &state.wallet).await?;
+            logger.success(format!(
+                "Created nonce account {} (authority {}) in tx {}",
+                nonce_account,
+                state.wallet.pubkey(),
+                signature
+            ));
+            logger.info(format!("Set NONCE_ACCOUNT={} to sign swaps with it", nonce_account));
+            Ok(())
+        }
+        Some("show") => {
+            let nonce_account = match args.get(1) {
+                Some(nonce_account) => Pubkey::from_str(nonce_account)?,
+                None => state.nonce.as_ref().map(|nonce| nonce.account).ok_or_else(|| anyhow!("NONCE_ACCOUNT not set. {}", USAGE))?,
+            };
+            let info = get_nonce_info(&state.rpc_nonblocking_client, &nonce_account).await?;
+            logger.info(format!(
+                "\n   * [NONCE ACCOUNT] => {}\n   * [AUTHORITY] => {}{}\n   * [NONCE] => {}\n   * [FEE] => {} lamports/signature\n   * [BALANCE] => {} SOL",
+                info.nonce_account,
+                info.authority,
+                if info.authority == state.wallet.pubkey() { " (bot wallet)" } else { " (NOT the bot wallet)" },
+                info.blockhash,
+                info.lamports_per_signature,
+                info.balance as f64 / 1_000_000_000.0
+            ));
+            Ok(())
+        }
+        _ => Err(anyhow!(USAGE)),
+    }
+}
@@ -0,0 +1,77 @@
+use {
+    crate::{
+        common::logger::Logger,
+        engine::{
+            journal::{journal_path, read_journal},
+            report::{PnlReport, PnlStats},
+        },
+    },
+    anyhow::{anyhow, Result},
+    std::{collections::BTreeMap, path::PathBuf},
+};
+
+const USAGE: &str = "Usage: pnl [--csv OUTPUT] [JOURNAL_PATH]";
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let logger = Logger::new("[PNL]".to_string());
+
+    let mut csv_path = None;
+    let mut path = journal_path();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--csv" => csv_path = Some(args.next().ok_or_else(|| anyhow!(USAGE))?.clone()),
+            arg if arg.starts_with("--") => return Err(anyhow!(USAGE)),
+            arg => path = PathBuf::from(arg),
+        }
+    }
+
+    let records = read_journal(&path)?;
+    let report = PnlReport::from_records(&records);
+    logger.info(format!("{} journal records from {}", records.len(), path.display()));
+
+    log_group(&logger, "TOTAL", &BTreeMap::from([("all".to_string(), report.total.clone())]));
+    log_group(&logger, "BY TARGET", &report.by_target);
+    log_group(&logger, "BY MINT", &report.by_mint);
+    log_group(&logger, "BY DAY", &report.by_day);
+    for position in report.positions.values().filter(|position| position.token_amount > 0) {
+        logger.info(format!(
+            "[OPEN] => {}: {} tokens, cost {} SOL",
+            position.mint,
+            position.token_amount,
+            position.cost_basis as f64 / 1_000_000_000.0
+        ));
+    }
+
+    if let Some(csv_path) = csv_path {
+        std::fs::write(&csv_path, report.to_csv())?;
+        logger.success(format!("Wrote {}", csv_path));
+    }
+    Ok(())
+}
+
+pub fn log_group(logger: &Logger, name: &str, rows: &BTreeMap<String, PnlStats>) {
+    for (key, stats) in rows {
+        logger.info(format!(
+            "[{}] => {}: realized {} SOL, win rate {} ({}/{}), avg hold {}, avg slippage {}, fees {} SOL, landed {}/{} ({})",
+            name,
+            key,
+            stats.realized_pnl as f64 / 1_000_000_000.0,
+            format_optional(stats.win_rate(), "%"),
+            stats.wins,
+            stats.closed,
+            format_optional(stats.average_hold_secs(), "s"),
+            format_optional(stats.average_slippage(), "%"),
+            stats.fees as f64 / 1_000_000_000.0,
+            stats.landed,
+            stats.sent,
+            format_optional(stats.landing_rate(), "%"),
+        ));
+    }
+}
+
+fn format_optional(value: Option<f64>, unit: &str) -> String {
+    value
+        .map(|value| format!("{:.1}{}", value, unit))
+        .unwrap_or_else(|| "-".to_string())
+}
@@ -0,0 +1,145 @@
+use {
+    crate::{
+        common::logger::Logger,
+        engine::monitor::{
+            recorder::{expected_path, read_recorded, recorded_files},
+            wallet_monitor::{extract_transaction_info, process_transaction},
+        },
+    },
+    anyhow::{anyhow, Result},
+    serde::{Deserialize, Serialize},
+    solana_transaction_status::EncodedTransaction,
+    std::{fs, path::Path},
+};
+
+const USAGE: &str = "Usage: replay [--update] PATH...";
+
+// What the decoder made of one recorded transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayOutcome {
+    pub signature: Option<String>,
+    pub pump: bool,
+    pub mint: Option<String>,
+    pub is_buy: Option<bool>,
+    pub error: Option<String>,
+}
+
+// Feeds recorded transactions through the same decoding the monitor uses and
+// compares the result with `<signature>.expected.json` next to each one.
+// `--update` writes the current decoding as the new expectation.
+pub async fn run(args: &[String]) -> Result<()> {
+    let logger = Logger::new("[REPLAY]".to_string());
+
+    let mut update = false;
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--update" => update = true,
+            arg if arg.starts_with("--") => return Err(anyhow!(USAGE)),
+            arg => paths.push(arg.to_string()),
+        }
+    }
+    if paths.is_empty() {
+        return Err(anyhow!(USAGE));
+    }
+
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+    for path in &paths {
+        for file in recorded_files(path)? {
+            let outcome = replay_file(&file).await?;
+            let expected_file = expected_path(&file);
+
+            if update {
+                fs::write(&expected_file, serde_json::to_string_pretty(&outcome)? + "\n")?;
+                logger.info(format!("[UPDATED] => {}", expected_file.display()));
+                continue;
+            }
+            if !expected_file.exists() {
+                logger.warning(format!("[NO EXPECTATION] => {} - {:?}", file.display(), outcome));
+                missing += 1;
+                continue;
+            }
+
+            let expected: ReplayOutcome = serde_json::from_str(&fs::read_to_string(&expected_file)?)
+                .map_err(|e| anyhow!("Failed to parse {}: {}", expected_file.display(), e))?;
+            if outcome == expected {
+                passed += 1;
+            } else {
+                logger.error(format!(
+                    "[MISMATCH] => {}\n   * expected: {:?}\n   * decoded:  {:?}",
+                    file.display(),
+                    expected,
+                    outcome
+                ));
+                failed += 1;
+            }
+        }
+    }
+
+    if update {
+        return Ok(());
+    }
+    logger.info(format!("{} passed, {} failed, {} without expectations", passed, failed, missing));
+    if failed > 0 {
+        return Err(anyhow!("{} recorded transactions decoded differently than expected", failed));
+    }
+    Ok(())
+}
+
+async fn replay_file(file: &Path) -> Result<ReplayOutcome> {
+    let recorded = read_recorded(file)?;
+    let transaction = &recorded.transaction;
+
+    let signature = match &transaction.transaction {
+        EncodedTransaction::Json(tx_data) => tx_data.signatures.first().cloned(),
+        _ => None,
+    };
+    let mut outcome = ReplayOutcome {
+        signature,
+        pump: false,
+        mint: None,
+        is_buy: None,
+        error: None,
+    };
+
+    match process_transaction(transaction).await {
+        Ok(Some(_)) => outcome.pump = true,
+        Ok(None) => return Ok(outcome),
+        Err(e) => {
+            outcome.pump = true;
+            outcome.error = Some(e.to_string());
+            return Ok(outcome);
+        }
+    }
+
+    match extract_transaction_info(transaction) {
+        Ok((mint, is_buy)) => {
+            outcome.mint = Some(mint);
+            outcome.is_buy = Some(is_buy);
+        }
+        Err(e) => outcome.error = Some(e.to_string()),
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn recorded_fixtures_decode_as_expected() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/transactions");
+        let files = recorded_files(&fixtures).unwrap();
+        assert!(!files.is_empty(), "no fixtures under {}", fixtures.display());
+
+        for file in files {
+            let expected_file = expected_path(&file);
+            let expected: ReplayOutcome = serde_json::from_str(
+                &fs::read_to_string(&expected_file)
+                    .unwrap_or_else(|e| panic!("{}: {}", expected_file.display(), e)),
+            )
+            .unwrap();
+            assert_eq!(replay_file(&file).await.unwrap(), expected, "{}", file.display());
+        }
+    }
+}
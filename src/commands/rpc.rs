@@ -0,0 +1,20 @@
+use {
+    crate::common::{logger::Logger, utils::AppState},
+    anyhow::{anyhow, Result},
+};
+
+const USAGE: &str = "Usage: rpc status";
+
+pub async fn run(args: &[String], state: &AppState) -> Result<()> {
+    let logger = Logger::new("[RPC]".to_string());
+
+    match args.first().map(String::as_str) {
+        Some("status") => {
+            logger.info(format!("Probing {} RPC endpoints...", state.rpc_pool.endpoint_count()));
+            state.rpc_pool.check_health().await;
+            state.rpc_pool.log_health();
+            Ok(())
+        }
+        _ => Err(anyhow!(USAGE)),
+    }
+}
@@ -0,0 +1,67 @@
+use {
+    crate::common::logger::Logger,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{commitment_config::CommitmentConfig, hash::Hash},
+    std::{
+        sync::{Arc, RwLock},
+        time::{Duration, Instant},
+    },
+};
+
+const BLOCKHASH_REFRESH_INTERVAL: u64 = 400; // milliseconds
+const BLOCKHASH_MAX_AGE: u64 = 10; // seconds
+
+#[derive(Debug, Clone, Copy)]
+pub struct CachedBlockhash {
+    pub blockhash: Hash,
+    pub last_valid_block_height: u64,
+    pub fetched_at: Instant,
+}
+
+// Latest blockhash kept fresh by a background task so signing a swap doesn't
+// wait on a get_latest_blockhash round trip.
+#[derive(Clone, Default)]
+pub struct BlockhashCache {
+    latest: Arc<RwLock<Option<CachedBlockhash>>>,
+}
+
+impl BlockhashCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Returns the cached blockhash unless the refresher has fallen behind.
+    pub fn get(&self) -> Option<CachedBlockhash> {
+        let latest = (*self.latest.read().ok()?)?;
+        if latest.fetched_at.elapsed() > Duration::from_secs(BLOCKHASH_MAX_AGE) {
+            return None;
+        }
+        Some(latest)
+    }
+
+    pub fn set(&self, blockhash: Hash, last_valid_block_height: u64) {
+        if let Ok(mut latest) = self.latest.write() {
+            *latest = Some(CachedBlockhash {
+                blockhash,
+                last_valid_block_height,
+                fetched_at: Instant::now(),
+            });
+        }
+    }
+
+    pub async fn run(self, client: Arc<RpcClient>) {
+        let logger = Logger::new("[BLOCKHASH]".to_string());
+        let mut interval = tokio::time::interval(Duration::from_millis(BLOCKHASH_REFRESH_INTERVAL));
+
+        loop {
+            interval.tick().await;
+            match client
+                .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+                .await
+            {
+                Ok((blockhash, last_valid_block_height)) => self.set(blockhash, last_valid_block_height),
+                Err(e) => logger.warning(format!("Failed to refresh blockhash: {}", e)),
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use {
+    crate::common::utils::import_optional_env_var,
+    anyhow::{anyhow, Result},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{
+        address_lookup_table::{
+            instruction::{create_lookup_table, extend_lookup_table},
+            state::AddressLookupTable,
+            AddressLookupTableAccount,
+        },
+        commitment_config::CommitmentConfig,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature, Signer},
+        transaction::Transaction,
+    },
+    std::str::FromStr,
+};
+
+// Address lookup table to compile swaps against, from `LOOKUP_TABLE`.
+pub fn import_lookup_table() -> Result<Option<Pubkey>> {
+    import_optional_env_var("LOOKUP_TABLE")
+        .map(|lookup_table| {
+            Pubkey::from_str(&lookup_table).map_err(|e| anyhow!("Invalid LOOKUP_TABLE: {}", e))
+        })
+        .transpose()
+}
+
+pub async fn get_lookup_table(client: &RpcClient, lookup_table: &Pubkey) -> Result<AddressLookupTableAccount> {
+    let data = client.get_account_data(lookup_table).await?;
+    let table = AddressLookupTable::deserialize(&data)
+        .map_err(|e| anyhow!("Failed to decode lookup table {}: {}", lookup_table, e))?;
+
+    Ok(AddressLookupTableAccount {
+        key: *lookup_table,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+// Creates an empty lookup table with the wallet as authority and payer.
+pub async fn create_wallet_lookup_table(client: &RpcClient, wallet: &Keypair) -> Result<(Pubkey, Signature)> {
+    let recent_slot = client
+        .get_slot_with_commitment(CommitmentConfig::finalized())
+        .await?;
+    let (instruction, lookup_table) = create_lookup_table(wallet.pubkey(), wallet.pubkey(), recent_slot);
+
+    let signature = send_wallet_transaction(client, wallet, &[instruction]).await?;
+    Ok((lookup_table, signature))
+}
+
+// Adds whichever of `addresses` the table doesn't hold yet. Returns `None` when
+// there was nothing to add.
+pub async fn extend_wallet_lookup_table(
+    client: &RpcClient,
+    wallet: &Keypair,
+    lookup_table: &Pubkey,
+    addresses: &[Pubkey],
+) -> Result<Option<Signature>> {
+    let existing = get_lookup_table(client, lookup_table).await?.addresses;
+    let missing: Vec<Pubkey> = addresses
+        .iter()
+        .filter(|address| !existing.contains(address))
+        .cloned()
+        .collect();
+    if missing.is_empty() {
+        return Ok(None);
+    }
+
+    let instruction = extend_lookup_table(*lookup_table, wallet.pubkey(), Some(wallet.pubkey()), missing);
+    Ok(Some(send_wallet_transaction(client, wallet, &[instruction]).await?))
+}
+
+async fn send_wallet_transaction(
+    client: &RpcClient,
+    wallet: &Keypair,
+    instructions: &[solana_sdk::instruction::Instruction],
+) -> Result<Signature> {
+    let recent_blockhash = client.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&wallet.pubkey()),
+        &[wallet],
+        recent_blockhash,
+    );
+    Ok(client.send_and_confirm_transaction(&transaction).await?)
+}
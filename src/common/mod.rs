@@ -1,2 +1,7 @@
+pub mod blockhash;
 pub mod logger;
+pub mod lookup_table;
+pub mod nonce;
+pub mod rpc_pool;
+pub mod token_metadata;
 pub mod utils;
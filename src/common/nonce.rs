@@ -0,0 +1,178 @@
+use {
+    crate::common::utils::import_optional_env_var,
+    anyhow::{anyhow, Result},
+    solana_client::{nonblocking::rpc_client::RpcClient, nonce_utils},
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        hash::Hash,
+        nonce::State,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature, Signer},
+        system_instruction,
+        transaction::Transaction,
+    },
+    std::{str::FromStr, sync::Arc},
+    tokio::sync::{Mutex, OwnedMutexGuard},
+};
+
+#[derive(Debug, Clone)]
+pub struct NonceInfo {
+    pub nonce_account: Pubkey,
+    pub authority: Pubkey,
+    pub blockhash: Hash,
+    pub lamports_per_signature: u64,
+    pub balance: u64,
+}
+
+// The durable nonce account swaps are signed against. Every nonce swap uses
+// the account's current value, so only one of them can land per value: a swap
+// leases the nonce and holds the lease until its value has been consumed.
+// The value itself is cached under the lease so a swap doesn't wait on a
+// getAccountInfo round trip; it is refreshed after each swap resolves.
+#[derive(Clone)]
+pub struct DurableNonce {
+    pub account: Pubkey,
+    cached: Arc<Mutex<Option<Hash>>>,
+}
+
+impl DurableNonce {
+    pub fn new(account: Pubkey) -> Self {
+        Self {
+            account,
+            cached: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // Waits for the swap holding the nonce, on any mint, to resolve.
+    pub async fn lease(&self) -> NonceLease {
+        NonceLease {
+            account: self.account,
+            cached: self.cached.clone().lock_owned().await,
+        }
+    }
+}
+
+pub struct NonceLease {
+    pub account: Pubkey,
+    cached: OwnedMutexGuard<Option<Hash>>,
+}
+
+impl NonceLease {
+    // Takes the cached value, fetching it when there is none. A lease dropped
+    // without `refresh` (the swap failed before it was sent) leaves the cache
+    // empty, so a stale value is never reused.
+    pub async fn current(&mut self, client: &RpcClient) -> Result<Hash> {
+        match self.cached.take() {
+            Some(nonce) => Ok(nonce),
+            None => self.fetch(client).await,
+        }
+    }
+
+    async fn fetch(&self, client: &RpcClient) -> Result<Hash> {
+        Ok(get_nonce_info(client, &self.account).await?.blockhash)
+    }
+
+    // Caches the value that replaced `used` once the swap signed against it
+    // has resolved, releasing the lease when done. A value that has not moved
+    // on yet is left for the next swap to fetch.
+    pub fn refresh(self, client: Arc<RpcClient>, used: Hash) {
+        tokio::spawn(async move {
+            let mut lease = self;
+            if let Ok(nonce) = lease.fetch(&client).await {
+                if nonce != used {
+                    *lease.cached = Some(nonce);
+                }
+            }
+        });
+    }
+
+    // Advances the nonce if it still holds `nonce`, so that no transaction
+    // signed against that value can land any more.
+    pub async fn invalidate(&self, client: &RpcClient, wallet: &Keypair, nonce: Hash) -> Result<()> {
+        if self.fetch(client).await? != nonce {
+            return Ok(());
+        }
+
+        let instruction = system_instruction::advance_nonce_account(&self.account, &wallet.pubkey());
+        let recent_blockhash = client.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&wallet.pubkey()),
+            &[wallet],
+            recent_blockhash,
+        );
+        client.send_and_confirm_transaction(&transaction).await?;
+        Ok(())
+    }
+}
+
+// Durable nonce account to sign swaps against, from `NONCE_ACCOUNT`.
+pub fn import_nonce_account() -> Result<Option<Pubkey>> {
+    import_optional_env_var("NONCE_ACCOUNT")
+        .map(|nonce_account| {
+            Pubkey::from_str(&nonce_account).map_err(|e| anyhow!("Invalid NONCE_ACCOUNT: {}", e))
+        })
+        .transpose()
+}
+
+pub async fn get_nonce_info(client: &RpcClient, nonce_account: &Pubkey) -> Result<NonceInfo> {
+    let account = nonce_utils::nonblocking::get_account_with_commitment(
+        client,
+        nonce_account,
+        CommitmentConfig::confirmed(),
+    )
+    .await?;
+    let data = nonce_utils::data_from_account(&account)?;
+
+    Ok(NonceInfo {
+        nonce_account: *nonce_account,
+        authority: data.authority,
+        blockhash: data.blockhash(),
+        lamports_per_signature: data.get_lamports_per_signature(),
+        balance: account.lamports,
+    })
+}
+
+// Creates a rent-exempt nonce account with the wallet as its authority.
+pub async fn create_nonce_account(client: &RpcClient, wallet: &Keypair) -> Result<(Pubkey, Signature)> {
+    let nonce_keypair = Keypair::new();
+    let lamports = client.get_minimum_balance_for_rent_exemption(State::size()).await?;
+    let instructions = system_instruction::create_nonce_account(
+        &wallet.pubkey(),
+        &nonce_keypair.pubkey(),
+        &wallet.pubkey(),
+        lamports,
+    );
+
+    let recent_blockhash = client.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&wallet.pubkey()),
+        &[wallet, &nonce_keypair],
+        recent_blockhash,
+    );
+    let signature = client.send_and_confirm_transaction(&transaction).await?;
+
+    Ok((nonce_keypair.pubkey(), signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn lease_waits_for_the_swap_holding_the_nonce() {
+        let nonce = DurableNonce::new(Pubkey::new_unique());
+        let held = nonce.lease().await;
+
+        let waiting = tokio::spawn({
+            let nonce = nonce.clone();
+            async move { nonce.lease().await.account }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+
+        drop(held);
+        assert_eq!(waiting.await.unwrap(), nonce.account);
+    }
+}
@@ -0,0 +1,529 @@
+use {
+    crate::common::{
+        logger::Logger,
+        utils::{import_env_var, import_optional_env_var},
+    },
+    async_trait::async_trait,
+    futures::future::join_all,
+    serde_json::Value,
+    solana_client::{
+        client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+        nonblocking::rpc_client::RpcClient,
+        rpc_client::RpcClientConfig,
+        rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+        rpc_request::{RpcError, RpcRequest},
+        rpc_sender::{RpcSender, RpcTransportStats},
+    },
+    solana_sdk::commitment_config::CommitmentConfig,
+    std::{
+        fmt,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+const FAILURES_BEFORE_COOLDOWN: u32 = 3;
+const COOLDOWN: u64 = 30; // seconds
+const HEALTH_CHECK_INTERVAL: u64 = 10; // seconds
+// Endpoints further behind the best known slot are only used as a last resort
+const MAX_SLOT_LAG: u64 = 25; // slots
+// Weight of the newest sample in the latency average
+const LATENCY_WEIGHT: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcRole {
+    Read,
+    Send,
+}
+
+impl RpcRole {
+    fn of(request: &RpcRequest) -> Self {
+        match request {
+            RpcRequest::SendTransaction => RpcRole::Send,
+            _ => RpcRole::Read,
+        }
+    }
+}
+
+impl fmt::Display for RpcRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcRole::Read => write!(f, "read"),
+            RpcRole::Send => write!(f, "send"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EndpointHealth {
+    pub requests: u64,
+    pub errors: u64,
+    pub consecutive_errors: u32,
+    pub latency_ms: Option<f64>,
+    pub slot: Option<u64>,
+    pub last_error: Option<String>,
+    cooldown_until: Option<Instant>,
+}
+
+impl EndpointHealth {
+    pub fn cooling_down(&self) -> bool {
+        self.cooldown_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    pub fn slot_lag(&self, best_slot: Option<u64>) -> Option<u64> {
+        Some(best_slot?.saturating_sub(self.slot?))
+    }
+
+    fn record_success(&mut self, elapsed: Duration) {
+        let latency_ms = elapsed.as_secs_f64() * 1000.0;
+        self.requests += 1;
+        self.consecutive_errors = 0;
+        self.cooldown_until = None;
+        self.latency_ms = Some(match self.latency_ms {
+            Some(average) => average + LATENCY_WEIGHT * (latency_ms - average),
+            None => latency_ms,
+        });
+    }
+
+    // Returns true when this failure puts the endpoint into cooldown. An
+    // endpoint still failing once its cooldown is over cools down again on the
+    // next failure; one failing while cooling down only has it extended.
+    fn record_failure(&mut self, error: String) -> bool {
+        self.requests += 1;
+        self.errors += 1;
+        self.consecutive_errors += 1;
+        self.last_error = Some(error);
+        if self.consecutive_errors >= FAILURES_BEFORE_COOLDOWN {
+            let cooling_down = self.cooling_down();
+            self.cooldown_until = Some(Instant::now() + Duration::from_secs(COOLDOWN));
+            return !cooling_down;
+        }
+        false
+    }
+}
+
+struct RpcEndpoint {
+    name: String,
+    url: String,
+    roles: Vec<RpcRole>,
+    client: RpcClient,
+    health: Mutex<EndpointHealth>,
+}
+
+impl RpcEndpoint {
+    fn health(&self) -> EndpointHealth {
+        self.health.lock().map(|health| health.clone()).unwrap_or_default()
+    }
+
+    fn update<T>(&self, update: impl FnOnce(&mut EndpointHealth) -> T) -> Option<T> {
+        self.health.lock().ok().map(|mut health| update(&mut health))
+    }
+}
+
+// RPC endpoints behind a single `RpcClient`. Reads and sends each go to the
+// healthiest, fastest endpoint holding that role and fail over to the next one
+// when an endpoint is unreachable or reports itself unhealthy.
+#[derive(Clone)]
+pub struct RpcPool {
+    endpoints: Arc<Vec<RpcEndpoint>>,
+}
+
+impl RpcPool {
+    // `RPC_HTTPS` serves both roles unless `RPC_READ_URLS` / `RPC_SEND_URLS`
+    // (comma-separated) list dedicated endpoints for that role.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let primary = import_env_var("RPC_HTTPS");
+        let read_urls = import_url_list("RPC_READ_URLS").unwrap_or_else(|| vec![primary.clone()]);
+        let send_urls = import_url_list("RPC_SEND_URLS").unwrap_or_else(|| vec![primary.clone()]);
+
+        let mut endpoints: Vec<(String, Vec<RpcRole>)> = Vec::new();
+        for (role, urls) in [(RpcRole::Read, read_urls), (RpcRole::Send, send_urls)] {
+            for url in urls {
+                match endpoints.iter_mut().find(|(existing, _)| *existing == url) {
+                    Some((_, roles)) => roles.push(role),
+                    None => endpoints.push((url, vec![role])),
+                }
+            }
+        }
+        Ok(Self::new(endpoints))
+    }
+
+    pub fn new(endpoints: Vec<(String, Vec<RpcRole>)>) -> Self {
+        Self::with_clients(
+            endpoints
+                .into_iter()
+                .map(|(url, roles)| {
+                    let client = RpcClient::new_with_commitment(url.clone(), CommitmentConfig::confirmed());
+                    (url, roles, client)
+                })
+                .collect(),
+        )
+    }
+
+    fn with_clients(endpoints: Vec<(String, Vec<RpcRole>, RpcClient)>) -> Self {
+        let endpoints = endpoints
+            .into_iter()
+            .enumerate()
+            .map(|(index, (url, roles, client))| RpcEndpoint {
+                name: endpoint_name(index, &url),
+                client,
+                url,
+                roles,
+                health: Mutex::new(EndpointHealth::default()),
+            })
+            .collect();
+        Self {
+            endpoints: Arc::new(endpoints),
+        }
+    }
+
+    pub fn client(&self) -> RpcClient {
+        RpcClient::new_sender(self.clone(), RpcClientConfig::with_commitment(CommitmentConfig::confirmed()))
+    }
+
+    pub fn endpoint_count(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    // Endpoints holding `role`, best first: those neither cooling down nor
+    // lagging, by average latency, then the rest.
+    fn candidates(&self, role: RpcRole) -> Vec<&RpcEndpoint> {
+        let best_slot = self.best_slot();
+        let mut candidates: Vec<(bool, f64, &RpcEndpoint)> = self
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.roles.contains(&role))
+            .map(|endpoint| {
+                let health = endpoint.health();
+                let lagging = health.slot_lag(best_slot).is_some_and(|lag| lag > MAX_SLOT_LAG);
+                // Untried endpoints go first so they get measured
+                (health.cooling_down() || lagging, health.latency_ms.unwrap_or_default(), endpoint)
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        candidates.into_iter().map(|(_, _, endpoint)| endpoint).collect()
+    }
+
+    // Probes every endpoint with getSlot, which also brings endpoints out of
+    // cooldown once they answer again.
+    pub async fn check_health(&self) {
+        join_all(self.endpoints.iter().map(|endpoint| async move {
+            let start_time = Instant::now();
+            match endpoint.client.get_slot_with_commitment(CommitmentConfig::processed()).await {
+                Ok(slot) => {
+                    endpoint.update(|health| {
+                        health.record_success(start_time.elapsed());
+                        health.slot = Some(slot);
+                    });
+                }
+                Err(e) => self.record_failure(endpoint, &e),
+            }
+        }))
+        .await;
+    }
+
+    pub async fn run_health_checks(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(HEALTH_CHECK_INTERVAL));
+        loop {
+            interval.tick().await;
+            self.check_health().await;
+            if self.endpoint_count() > 1 {
+                self.log_health();
+            }
+        }
+    }
+
+    // Highest slot any endpoint reported in a health check
+    fn best_slot(&self) -> Option<u64> {
+        self.endpoints.iter().filter_map(|endpoint| endpoint.health().slot).max()
+    }
+
+    pub fn log_health(&self) {
+        let logger = Logger::new("[RPC POOL]".to_string());
+        let best_slot = self.best_slot();
+
+        for endpoint in self.endpoints.iter() {
+            let health = endpoint.health();
+            let slot_lag = health.slot_lag(best_slot);
+            let message = format!(
+                "[ENDPOINT] => {} ({}): latency {}, errors {}/{}, slot lag {}{}",
+                endpoint.name,
+                endpoint.roles.iter().map(RpcRole::to_string).collect::<Vec<_>>().join("+"),
+                health
+                    .latency_ms
+                    .map(|latency_ms| format!("{:.0}ms", latency_ms))
+                    .unwrap_or_else(|| "-".to_string()),
+                health.errors,
+                health.requests,
+                slot_lag.map(|lag| lag.to_string()).unwrap_or_else(|| "-".to_string()),
+                health
+                    .last_error
+                    .as_ref()
+                    .filter(|_| health.consecutive_errors > 0)
+                    .map(|error| format!(", last error: {}", error))
+                    .unwrap_or_default(),
+            );
+            if health.cooling_down() {
+                logger.warning(format!("{} [COOLING DOWN]", message));
+            } else if slot_lag.is_some_and(|lag| lag > MAX_SLOT_LAG) {
+                logger.warning(format!("{} [LAGGING]", message));
+            } else if health.consecutive_errors > 0 {
+                logger.warning(message);
+            } else {
+                logger.info(message);
+            }
+        }
+    }
+
+    fn record_failure(&self, endpoint: &RpcEndpoint, error: &ClientError) {
+        if endpoint.update(|health| health.record_failure(error.to_string())) == Some(true) {
+            Logger::new("[RPC POOL]".to_string()).warning(format!(
+                "[FAILOVER] => {} failed {} times in a row, cooling down for {}s: {}",
+                endpoint.name,
+                endpoint.health().consecutive_errors,
+                COOLDOWN,
+                error
+            ));
+        }
+    }
+}
+
+#[async_trait]
+impl RpcSender for RpcPool {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let role = RpcRole::of(&request);
+        let mut last_error = None;
+
+        for endpoint in self.candidates(role) {
+            let start_time = Instant::now();
+            match endpoint.client.send::<Value>(request, params.clone()).await {
+                // Answers that any node would give the same way count as healthy
+                Err(e) if is_endpoint_failure(&e) => {
+                    self.record_failure(endpoint, &e);
+                    last_error = Some(e);
+                }
+                result => {
+                    endpoint.update(|health| health.record_success(start_time.elapsed()));
+                    return result;
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            ClientErrorKind::RpcError(RpcError::RpcRequestError(format!("No {} RPC endpoint configured", role))).into()
+        }))
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        let mut stats = RpcTransportStats::default();
+        for endpoint in self.endpoints.iter() {
+            let endpoint_stats = endpoint.client.get_transport_stats();
+            stats.request_count += endpoint_stats.request_count;
+            stats.elapsed_time += endpoint_stats.elapsed_time;
+            stats.rate_limited_time += endpoint_stats.rate_limited_time;
+        }
+        stats
+    }
+
+    // The primary endpoint, for callers that need a URL
+    fn url(&self) -> String {
+        self.endpoints.first().map(|endpoint| endpoint.url.clone()).unwrap_or_default()
+    }
+}
+
+// Errors that say the endpoint itself is down, as opposed to errors about the
+// request that every endpoint would return.
+fn is_endpoint_failure(error: &ClientError) -> bool {
+    match error.kind() {
+        ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => true,
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => true,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+        }
+        _ => false,
+    }
+}
+
+fn import_url_list(key: &str) -> Option<Vec<String>> {
+    let urls: Vec<String> = import_optional_env_var(key)?
+        .split(',')
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .collect();
+    (!urls.is_empty()).then_some(urls)
+}
+
+// RPC URLs often carry an API key, so logs only show the host.
+fn endpoint_name(index: usize, url: &str) -> String {
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            let host = url.host_str()?;
+            Some(match url.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_string(),
+            })
+        })
+        .unwrap_or_else(|| "invalid url".to_string());
+    format!("#{} {}", index + 1, host)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    };
+
+    // Answers getSlot with `slot` while up, and every request with a
+    // connection error while down. `calls` counts requests either way.
+    struct StubSender {
+        url: String,
+        slot: u64,
+        up: Arc<AtomicBool>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl RpcSender for StubSender {
+        async fn send(&self, request: RpcRequest, _params: Value) -> ClientResult<Value> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if !self.up.load(Ordering::SeqCst) {
+                return Err(error());
+            }
+            match request {
+                // RpcClient asks for the node version before its first commitment-aware call
+                RpcRequest::GetVersion => Ok(serde_json::json!({ "solana-core": "1.18.26" })),
+                _ => Ok(Value::from(self.slot)),
+            }
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            self.url.clone()
+        }
+    }
+
+    struct Stub {
+        up: Arc<AtomicBool>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Stub {
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    fn stub_pool(endpoints: Vec<(&str, Vec<RpcRole>, u64, bool)>) -> (RpcPool, Vec<Stub>) {
+        let mut stubs = Vec::new();
+        let endpoints = endpoints
+            .into_iter()
+            .map(|(url, roles, slot, up)| {
+                let up = Arc::new(AtomicBool::new(up));
+                let calls = Arc::new(AtomicUsize::new(0));
+                stubs.push(Stub { up: up.clone(), calls: calls.clone() });
+                let sender = StubSender { url: url.to_string(), slot, up, calls };
+                let client =
+                    RpcClient::new_sender(sender, RpcClientConfig::with_commitment(CommitmentConfig::confirmed()));
+                (url.to_string(), roles, client)
+            })
+            .collect();
+        (RpcPool::with_clients(endpoints), stubs)
+    }
+
+    fn candidate_urls(pool: &RpcPool, role: RpcRole) -> Vec<&str> {
+        pool.candidates(role).into_iter().map(|endpoint| endpoint.url.as_str()).collect()
+    }
+
+    fn error() -> ClientError {
+        ClientErrorKind::Io(std::io::Error::other("connection refused")).into()
+    }
+
+    #[test]
+    fn candidates_hold_the_role_and_prefer_healthy_fast_endpoints() {
+        let (pool, _) = stub_pool(vec![
+            ("http://read", vec![RpcRole::Read], 100, true),
+            ("http://send", vec![RpcRole::Send], 100, true),
+            ("http://both", vec![RpcRole::Read, RpcRole::Send], 100, true),
+        ]);
+        assert_eq!(candidate_urls(&pool, RpcRole::Read), vec!["http://read", "http://both"]);
+        assert_eq!(candidate_urls(&pool, RpcRole::Send), vec!["http://send", "http://both"]);
+
+        pool.endpoints[0].update(|health| health.record_success(Duration::from_millis(50)));
+        pool.endpoints[2].update(|health| health.record_success(Duration::from_millis(10)));
+        assert_eq!(candidate_urls(&pool, RpcRole::Read), vec!["http://both", "http://read"]);
+
+        // Lagging or cooling down sends an endpoint to the back however fast it is
+        pool.endpoints[1].update(|health| health.slot = Some(100));
+        pool.endpoints[2].update(|health| health.slot = Some(100 - MAX_SLOT_LAG - 1));
+        assert_eq!(candidate_urls(&pool, RpcRole::Read), vec!["http://read", "http://both"]);
+        assert_eq!(candidate_urls(&pool, RpcRole::Send), vec!["http://send", "http://both"]);
+
+        pool.endpoints[2].update(|health| health.slot = Some(100));
+        assert_eq!(candidate_urls(&pool, RpcRole::Read), vec!["http://both", "http://read"]);
+        for _ in 0..FAILURES_BEFORE_COOLDOWN {
+            pool.record_failure(&pool.endpoints[2], &error());
+        }
+        assert_eq!(candidate_urls(&pool, RpcRole::Read), vec!["http://read", "http://both"]);
+    }
+
+    #[test]
+    fn endpoint_cools_down_again_after_cooldown_expires() {
+        let mut health = EndpointHealth::default();
+        for _ in 1..FAILURES_BEFORE_COOLDOWN {
+            assert!(!health.record_failure("down".to_string()));
+        }
+        assert!(!health.cooling_down());
+        assert!(health.record_failure("down".to_string()));
+        assert!(health.cooling_down());
+
+        // Failing again while cooling down extends it without re-entering
+        assert!(!health.record_failure("down".to_string()));
+        assert!(health.cooling_down());
+
+        // Still failing once the cooldown is over puts it straight back
+        health.cooldown_until = Some(Instant::now() - Duration::from_secs(1));
+        assert!(!health.cooling_down());
+        assert!(health.record_failure("down".to_string()));
+        assert!(health.cooling_down());
+
+        health.record_success(Duration::from_millis(10));
+        assert!(!health.cooling_down());
+        assert_eq!(health.consecutive_errors, 0);
+        assert!(!health.record_failure("down".to_string()));
+    }
+
+    #[tokio::test]
+    async fn requests_fail_over_to_the_next_endpoint() {
+        let (pool, stubs) = stub_pool(vec![
+            ("http://primary", vec![RpcRole::Read, RpcRole::Send], 100, false),
+            ("http://backup", vec![RpcRole::Read, RpcRole::Send], 200, true),
+        ]);
+        let client = pool.client();
+
+        // The backup answers while the primary fails its way into cooldown
+        while !pool.endpoints[0].health().cooling_down() {
+            assert_eq!(client.get_slot().await.unwrap(), 200);
+        }
+        assert_eq!(stubs[0].calls(), FAILURES_BEFORE_COOLDOWN as usize);
+
+        // A cooling endpoint is skipped while a healthy one answers
+        assert_eq!(client.get_slot().await.unwrap(), 200);
+        assert_eq!(stubs[0].calls(), FAILURES_BEFORE_COOLDOWN as usize);
+
+        // With every endpoint down the last error comes back
+        stubs[1].up.store(false, Ordering::SeqCst);
+        assert!(client.get_slot().await.is_err());
+        assert_eq!(stubs[0].calls(), FAILURES_BEFORE_COOLDOWN as usize + 1);
+
+        // A passing health check brings the primary back
+        stubs[0].up.store(true, Ordering::SeqCst);
+        pool.check_health().await;
+        assert!(!pool.endpoints[0].health().cooling_down());
+        assert_eq!(client.get_slot().await.unwrap(), 100);
+    }
+}
@@ -0,0 +1,129 @@
+use {
+    anyhow::{anyhow, Result},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::pubkey::Pubkey,
+    std::str::FromStr,
+};
+
+pub const METADATA_PROGRAM: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+// Leading fields of a Metaplex token metadata account.
+#[derive(Debug, Clone)]
+pub struct TokenMetadata {
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+pub fn get_metadata_pda(mint: &Pubkey) -> Result<Pubkey> {
+    let program_id = Pubkey::from_str(METADATA_PROGRAM)?;
+    let seeds = [b"metadata".as_ref(), program_id.as_ref(), mint.as_ref()];
+    let (metadata, _bump) = Pubkey::find_program_address(&seeds, &program_id);
+    Ok(metadata)
+}
+
+pub async fn get_token_metadata(client: &RpcClient, mint: &Pubkey) -> Result<TokenMetadata> {
+    let data = client.get_account_data(&get_metadata_pda(mint)?).await?;
+    decode_token_metadata(&data)
+}
+
+// Reads key, update authority, mint, then the borsh strings name, symbol and
+// uri, which Metaplex pads with NULs to a fixed length.
+pub fn decode_token_metadata(data: &[u8]) -> Result<TokenMetadata> {
+    let mut offset = 1;
+    let update_authority = read_pubkey(data, &mut offset)?;
+    let mint = read_pubkey(data, &mut offset)?;
+    let name = read_string(data, &mut offset)?;
+    let symbol = read_string(data, &mut offset)?;
+    let uri = read_string(data, &mut offset)?;
+
+    Ok(TokenMetadata {
+        update_authority,
+        mint,
+        name,
+        symbol,
+        uri,
+    })
+}
+
+fn read_pubkey(data: &[u8], offset: &mut usize) -> Result<Pubkey> {
+    let bytes = data
+        .get(*offset..*offset + 32)
+        .ok_or_else(|| anyhow!("Metadata account too short"))?;
+    *offset += 32;
+    Ok(Pubkey::try_from(bytes)?)
+}
+
+fn read_string(data: &[u8], offset: &mut usize) -> Result<String> {
+    let len_bytes = data
+        .get(*offset..*offset + 4)
+        .ok_or_else(|| anyhow!("Metadata account too short"))?;
+    let len = u32::from_le_bytes(len_bytes.try_into()?) as usize;
+    *offset += 4;
+    let bytes = data
+        .get(*offset..*offset + len)
+        .ok_or_else(|| anyhow!("Metadata account too short"))?;
+    *offset += len;
+    Ok(String::from_utf8_lossy(bytes).trim_end_matches('\0').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Pump's metadata update authority
+    const PUMP_METADATA_AUTHORITY: &str = "TSLvdd1pWpHVjahSpsvCXUbgwsL3JAcvokwaKt1eokM";
+    const MINT: &str = "3x4YYzN2Fjc6PzGoH4mb6rHFA96hVWLKD6oYpKJrpump";
+    // Size of a MetadataV1 account as created by Metaplex
+    const METADATA_ACCOUNT_LEN: usize = 679;
+
+    fn padded_string(value: &str, len: usize) -> Vec<u8> {
+        let mut bytes = (len as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(value.as_bytes());
+        bytes.resize(4 + len, 0);
+        bytes
+    }
+
+    // A metadata account laid out the way Metaplex writes one for a Pump
+    // token: fixed-width name, symbol and uri, then the fields we don't read.
+    fn metadata_account() -> Vec<u8> {
+        let mut data = vec![4]; // Key::MetadataV1
+        data.extend_from_slice(Pubkey::from_str(PUMP_METADATA_AUTHORITY).unwrap().as_ref());
+        data.extend_from_slice(Pubkey::from_str(MINT).unwrap().as_ref());
+        data.extend(padded_string("Pepe Coin", 32));
+        data.extend(padded_string("PEPE", 10));
+        data.extend(padded_string("https://ipfs.io/ipfs/QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG", 200));
+        data.extend_from_slice(&0u16.to_le_bytes()); // seller_fee_basis_points
+        data.push(0); // creators: None
+        data.push(0); // primary_sale_happened
+        data.push(0); // is_mutable
+        data.extend_from_slice(&[1, 254]); // edition_nonce: Some(254)
+        data.extend_from_slice(&[1, 2]); // token_standard: Some(Fungible)
+        data.resize(METADATA_ACCOUNT_LEN, 0);
+        data
+    }
+
+    #[test]
+    fn decodes_a_metaplex_metadata_account() {
+        let metadata = decode_token_metadata(&metadata_account()).unwrap();
+
+        assert_eq!(metadata.update_authority.to_string(), PUMP_METADATA_AUTHORITY);
+        assert_eq!(metadata.mint.to_string(), MINT);
+        assert_eq!(metadata.name, "Pepe Coin");
+        assert_eq!(metadata.symbol, "PEPE");
+        assert_eq!(metadata.uri, "https://ipfs.io/ipfs/QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG");
+    }
+
+    #[test]
+    fn rejects_a_truncated_account() {
+        let data = metadata_account();
+
+        // Cut off inside the uri, and inside the mint
+        for len in [1 + 32 + 32 + 36 + 14 + 100, 40] {
+            let error = decode_token_metadata(&data[..len]).unwrap_err();
+            assert_eq!(error.to_string(), "Metadata account too short");
+        }
+    }
+}
@@ -1,5 +1,10 @@
-use anyhow::Result;
-use solana_sdk::{commitment_config::CommitmentConfig, signature::Keypair};
+use crate::{
+    common::{blockhash::BlockhashCache, nonce::DurableNonce, rpc_pool::RpcPool},
+    services::jito::TipConfig,
+};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use solana_sdk::{address_lookup_table::AddressLookupTableAccount, signature::Keypair};
 use std::{env, sync::Arc};
 
 #[derive(Debug, Clone)]
@@ -18,9 +23,14 @@ pub enum SwapDirection {
 
 #[derive(Clone)]
 pub struct AppState {
-    pub rpc_client: Arc<solana_client::rpc_client::RpcClient>,
     pub rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
+    pub rpc_pool: RpcPool,
     pub wallet: Arc<Keypair>,
+    pub blockhash_cache: BlockhashCache,
+    // Durable nonce account to sign swaps against, from `NONCE_ACCOUNT`
+    pub nonce: Option<DurableNonce>,
+    pub lookup_table: Option<AddressLookupTableAccount>,
+    pub tip_config: TipConfig,
 }
 
 
@@ -28,24 +38,37 @@ pub fn import_env_var(key: &str) -> String {
     env::var(key).unwrap_or_else(|_| panic!("Environment variable {} not set", key))
 }
 
+// Optional settings are left blank in `.env.example`, so empty counts as unset.
+pub fn import_optional_env_var(key: &str) -> Option<String> {
+    env::var(key).ok().filter(|value| !value.trim().is_empty())
+}
+
 pub fn import_wallet() -> Result<Arc<Keypair>> {
     let priv_key = import_env_var("PRIVATE_KEY");
-    let wallet = Keypair::from_base58_string(&priv_wallet);
+    let wallet = Keypair::from_base58_string(&priv_key);
     Ok(Arc::new(wallet))
 }
 
-pub async fn create_nonblocking_rpc_client() -> Result<solana_client::nonblocking::rpc_client::RpcClient> {
-    let rpc_url = import_env_var("RPC_HTTPS");
-    Ok(solana_client::nonblocking::rpc_client::RpcClient::new_with_commitment(
-        rpc_url,
-        CommitmentConfig::confirmed(),
-    ))
-}
+// Posts a JSON-RPC request and returns its `result`, turning an `error` reply
+// into an error.
+pub async fn post_json_rpc(url: &str, method: &str, params: Value) -> Result<Value> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+
+    let response: Value = reqwest::Client::new()
+        .post(url)
+        .json(&request)
+        .send()
+        .await?
+        .json()
+        .await?;
 
-pub fn create_rpc_client() -> Result<solana_client::rpc_client::RpcClient> {
-    let rpc_url = import_env_var("RPC_HTTPS");
-    Ok(solana_client::rpc_client::RpcClient::new_with_commitment(
-        rpc_url,
-        CommitmentConfig::confirmed(),
-    ))
+    if let Some(error) = response.get("error") {
+        return Err(anyhow!("{} failed: {}", method, error));
+    }
+    Ok(response["result"].clone())
 }
@@ -1 +1,2 @@
+pub mod pump_error;
 pub mod pump_fun;
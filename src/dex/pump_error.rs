@@ -0,0 +1,160 @@
+use {
+    solana_sdk::{instruction::InstructionError, transaction::TransactionError},
+    std::fmt,
+};
+
+// Anchor numbers custom program errors from 6000
+const PUMP_ERROR_OFFSET: u32 = 6000;
+
+// Custom errors of the Pump program, in IDL order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PumpError {
+    NotAuthorized,
+    AlreadyInitialized,
+    TooMuchSolRequired,
+    TooLittleSolReceived,
+    MintDoesNotMatchBondingCurve,
+    BondingCurveComplete,
+    BondingCurveNotComplete,
+    NotInitialized,
+    Unknown(u32),
+}
+
+impl PumpError {
+    pub fn from_code(code: u32) -> Option<Self> {
+        if code < PUMP_ERROR_OFFSET {
+            return None;
+        }
+        Some(match code - PUMP_ERROR_OFFSET {
+            0 => PumpError::NotAuthorized,
+            1 => PumpError::AlreadyInitialized,
+            2 => PumpError::TooMuchSolRequired,
+            3 => PumpError::TooLittleSolReceived,
+            4 => PumpError::MintDoesNotMatchBondingCurve,
+            5 => PumpError::BondingCurveComplete,
+            6 => PumpError::BondingCurveNotComplete,
+            7 => PumpError::NotInitialized,
+            _ => PumpError::Unknown(code),
+        })
+    }
+
+    pub fn code(&self) -> u32 {
+        match self {
+            PumpError::NotAuthorized => PUMP_ERROR_OFFSET,
+            PumpError::AlreadyInitialized => PUMP_ERROR_OFFSET + 1,
+            PumpError::TooMuchSolRequired => PUMP_ERROR_OFFSET + 2,
+            PumpError::TooLittleSolReceived => PUMP_ERROR_OFFSET + 3,
+            PumpError::MintDoesNotMatchBondingCurve => PUMP_ERROR_OFFSET + 4,
+            PumpError::BondingCurveComplete => PUMP_ERROR_OFFSET + 5,
+            PumpError::BondingCurveNotComplete => PUMP_ERROR_OFFSET + 6,
+            PumpError::NotInitialized => PUMP_ERROR_OFFSET + 7,
+            PumpError::Unknown(code) => *code,
+        }
+    }
+
+    // Stable machine-readable name, e.g. for the trade journal
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PumpError::NotAuthorized => "not_authorized",
+            PumpError::AlreadyInitialized => "already_initialized",
+            PumpError::TooMuchSolRequired => "too_much_sol_required",
+            PumpError::TooLittleSolReceived => "too_little_sol_received",
+            PumpError::MintDoesNotMatchBondingCurve => "mint_does_not_match_bonding_curve",
+            PumpError::BondingCurveComplete => "bonding_curve_complete",
+            PumpError::BondingCurveNotComplete => "bonding_curve_not_complete",
+            PumpError::NotInitialized => "not_initialized",
+            PumpError::Unknown(_) => "unknown",
+        }
+    }
+
+    // The swap's slippage bound was hit: a buy needed more SOL than allowed or
+    // a sell returned less than the minimum.
+    pub fn is_slippage(&self) -> bool {
+        matches!(self, PumpError::TooMuchSolRequired | PumpError::TooLittleSolReceived)
+    }
+
+    pub fn from_transaction_error(error: &TransactionError) -> Option<Self> {
+        match error {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => Self::from_code(*code),
+            _ => None,
+        }
+    }
+
+    // Finds the error in simulation or transaction logs, either from Anchor's
+    // "Error Number: 6002." line or a "custom program error: 0x1772" message.
+    pub fn from_logs(logs: &[String]) -> Option<Self> {
+        logs.iter().find_map(|log| Self::from_message(log))
+    }
+
+    pub fn from_message(message: &str) -> Option<Self> {
+        if let Some((_, rest)) = message.split_once("Error Number: ") {
+            let code: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            if let Ok(code) = code.parse::<u32>() {
+                return Self::from_code(code);
+            }
+        }
+        if let Some((_, rest)) = message.split_once("custom program error: 0x") {
+            let code: String = rest.chars().take_while(|c| c.is_ascii_hexdigit()).collect();
+            if let Ok(code) = u32::from_str_radix(&code, 16) {
+                return Self::from_code(code);
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for PumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PumpError::Unknown(code) => write!(f, "Pump error {}", code),
+            error => write!(f, "{:?} ({})", error, error.code()),
+        }
+    }
+}
+
+impl std::error::Error for PumpError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_message_reads_anchor_and_custom_program_codes() {
+        let anchor = "Program log: AnchorError thrown in programs/pump/src/lib.rs:673. \
+                      Error Code: TooMuchSolRequired. Error Number: 6002. Error Message: slippage.";
+        assert_eq!(PumpError::from_message(anchor), Some(PumpError::TooMuchSolRequired));
+        assert_eq!(
+            PumpError::from_message("Program failed: custom program error: 0x1773"),
+            Some(PumpError::TooLittleSolReceived)
+        );
+        assert_eq!(
+            PumpError::from_message("custom program error: 0x1775"),
+            Some(PumpError::BondingCurveComplete)
+        );
+    }
+
+    #[test]
+    fn from_message_keeps_unlisted_codes_and_ignores_non_anchor_errors() {
+        assert_eq!(PumpError::from_message("Error Number: 6042."), Some(PumpError::Unknown(6042)));
+        assert_eq!(PumpError::from_message("custom program error: 0x1"), None);
+        assert_eq!(PumpError::from_message("Program log: Instruction: Buy"), None);
+        assert_eq!(PumpError::from_message("Error Number: none"), None);
+    }
+
+    #[test]
+    fn codes_round_trip_and_slippage_errors_are_flagged() {
+        for code in 6000..6009 {
+            let error = PumpError::from_code(code).unwrap();
+            assert_eq!(error.code(), code);
+        }
+        assert!(PumpError::TooMuchSolRequired.is_slippage());
+        assert!(PumpError::TooLittleSolReceived.is_slippage());
+        assert!(!PumpError::BondingCurveComplete.is_slippage());
+
+        let logs = vec![
+            "Program log: Instruction: Sell".to_string(),
+            "Program log: Error Number: 6003.".to_string(),
+        ];
+        assert_eq!(PumpError::from_logs(&logs), Some(PumpError::TooLittleSolReceived));
+    }
+}
@@ -0,0 +1,724 @@
+use {
+    crate::{
+        common::{
+            blockhash::BlockhashCache,
+            logger::Logger,
+            nonce::{DurableNonce, NonceLease},
+            utils::{import_optional_env_var, SwapConfig, SwapDirection},
+        },
+        dex::pump_error::PumpError,
+        services::jito,
+    },
+    anyhow::{anyhow, Result},
+    borsh::BorshDeserialize,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{
+        address_lookup_table::AddressLookupTableAccount,
+        commitment_config::CommitmentConfig,
+        compute_budget::{self, ComputeBudgetInstruction},
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        message::{v0, Message, VersionedMessage},
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction, system_program,
+        transaction::VersionedTransaction,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address,
+        instruction::create_associated_token_account_idempotent,
+    },
+    std::{fmt, str::FromStr, sync::Arc, time::Duration},
+};
+
+pub const PUMP_PROGRAM: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+pub const PUMP_GLOBAL: &str = "4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf";
+pub const PUMP_FEE_RECIPIENT: &str = "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM";
+pub const PUMP_EVENT_AUTHORITY: &str = "Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1";
+pub const PUMP_BUY_METHOD: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+pub const PUMP_SELL_METHOD: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+pub const PUMP_TRADE_EVENT: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
+// Anchor's prefix on the data of an event emitted through a self-CPI
+pub const EVENT_IX_TAG: [u8; 8] = [228, 69, 165, 46, 81, 203, 154, 29];
+pub const BONDING_CURVE_SEED: &[u8] = b"bonding-curve";
+pub const PUMP_FEE_BPS: u64 = 100;
+// Real token reserves of a fresh curve; the curve completes once they're sold
+pub const PUMP_INITIAL_REAL_TOKEN_RESERVES: u64 = 793_100_000_000_000;
+// Every curve starts from these virtual reserves and supply
+pub const PUMP_INITIAL_VIRTUAL_TOKEN_RESERVES: u64 = 1_073_000_000_000_000;
+pub const PUMP_INITIAL_VIRTUAL_SOL_RESERVES: u64 = 30_000_000_000;
+pub const PUMP_TOKEN_TOTAL_SUPPLY: u64 = 1_000_000_000_000_000;
+
+const BONDING_CURVE_RETRIES: u32 = 3;
+const BONDING_CURVE_RETRY_DELAY: u64 = 200; // milliseconds
+const DEFAULT_UNIT_PRICE: u64 = 20_000; // micro-lamports
+const DEFAULT_UNIT_LIMIT: u32 = 200_000;
+
+// On-chain layout of the Pump bonding curve. Newer curves carry extra trailing
+// fields (e.g. the creator), so this is read as a prefix rather than with
+// `try_from_slice`, which rejects any unread bytes.
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct BondingCurveAccount {
+    pub discriminator: u64,
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+    // Only present on curves created after Pump added it
+    #[borsh(skip)]
+    pub creator: Option<Pubkey>,
+}
+
+impl BondingCurveAccount {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut rest = data;
+        let mut account = Self::deserialize(&mut rest)
+            .map_err(|e| anyhow!("Failed to decode bonding curve account: {}", e))?;
+        if rest.len() >= 32 {
+            account.creator = Pubkey::try_from(&rest[..32]).ok();
+        }
+        Ok(account)
+    }
+}
+
+// Anchor event the Pump program emits on every buy and sell, logged as
+// "Program data: <base64>". `sol_amount` excludes the Pump fee.
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct TradeEvent {
+    pub mint: Pubkey,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    pub user: Pubkey,
+    pub timestamp: i64,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+}
+
+impl TradeEvent {
+    // Lamports per raw token unit the trade executed at
+    pub fn price(&self) -> Option<f64> {
+        if self.token_amount == 0 {
+            return None;
+        }
+        Some(self.sol_amount as f64 / self.token_amount as f64)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PumpInfo {
+    pub mint: String,
+    pub bonding_curve: String,
+    pub associated_bonding_curve: String,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+    pub creator: Option<String>,
+}
+
+// Why a swap was skipped before sending: the simulated transaction failed.
+#[derive(Debug, Clone)]
+pub struct SimulationFailure {
+    pub error: String,
+    pub pump_error: Option<PumpError>,
+    pub units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+}
+
+impl fmt::Display for SimulationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Simulation failed: {} ({} CU)\n   * [SIMULATION LOGS] => {:?}",
+            self.pump_error.map(|e| e.to_string()).unwrap_or_else(|| self.error.clone()),
+            self.units_consumed.map(|units| units.to_string()).unwrap_or_else(|| "?".to_string()),
+            self.logs
+        )
+    }
+}
+
+impl std::error::Error for SimulationFailure {}
+
+pub struct Pump {
+    pub client: Arc<RpcClient>,
+    pub keypair: Arc<Keypair>,
+    pub blockhash_cache: BlockhashCache,
+    // When set, swaps are signed against this durable nonce instead of a
+    // recent blockhash, so only one of several variants can ever land
+    pub nonce: Option<DurableNonce>,
+    // When set, swaps are compiled as v0 messages against this table
+    pub lookup_table: Option<AddressLookupTableAccount>,
+}
+
+impl Pump {
+    pub fn new(
+        client: Arc<RpcClient>,
+        keypair: Arc<Keypair>,
+        blockhash_cache: BlockhashCache,
+        nonce: Option<DurableNonce>,
+        lookup_table: Option<AddressLookupTableAccount>,
+    ) -> Self {
+        Self {
+            client,
+            keypair,
+            blockhash_cache,
+            nonce,
+            lookup_table,
+        }
+    }
+
+    pub async fn get_token_balance(&self, mint: &str) -> Result<u64> {
+        let mint = Pubkey::from_str(mint)?;
+        let ata = get_associated_token_address(&self.keypair.pubkey(), &mint);
+        match self.client.get_token_account_balance(&ata).await {
+            Ok(balance) => Ok(balance.amount.parse::<u64>()?),
+            // No token account yet means nothing to sell
+            Err(_) => Ok(0),
+        }
+    }
+
+    // Re-reads the bonding curve of `pump_info` for fresh reserves.
+    pub async fn refresh_pump_info(&self, pump_info: &PumpInfo) -> Result<PumpInfo> {
+        let bonding_curve = Pubkey::from_str(&pump_info.bonding_curve)?;
+        let data = self.client.get_account_data(&bonding_curve).await?;
+        let account = BondingCurveAccount::decode(&data)?;
+
+        Ok(PumpInfo {
+            virtual_sol_reserves: account.virtual_sol_reserves,
+            virtual_token_reserves: account.virtual_token_reserves,
+            real_sol_reserves: account.real_sol_reserves,
+            real_token_reserves: account.real_token_reserves,
+            complete: account.complete,
+            ..pump_info.clone()
+        })
+    }
+
+    // Builds the swap instructions (compute budget, ATA, Pump buy/sell) without
+    // signing or sending them. `amount` is lamports to spend on buys and raw
+    // token units to sell on sells.
+    pub fn build_swap_instructions(&self, pump_info: &PumpInfo, config: &SwapConfig) -> Result<Vec<Instruction>> {
+        let owner = self.keypair.pubkey();
+        let mint = Pubkey::from_str(&pump_info.mint)?;
+        let bonding_curve = Pubkey::from_str(&pump_info.bonding_curve)?;
+        let associated_bonding_curve = Pubkey::from_str(&pump_info.associated_bonding_curve)?;
+        let user_ata = get_associated_token_address(&owner, &mint);
+
+        // The curve has migrated; the Pump program would reject the swap
+        if pump_info.complete {
+            return Err(PumpError::BondingCurveComplete.into());
+        }
+
+        let mut instructions = compute_budget_instructions();
+
+        match config.swap_direction {
+            SwapDirection::Buy => {
+                let token_amount = get_buy_token_amount(pump_info, config.amount);
+                if token_amount == 0 {
+                    return Err(anyhow!("Buy of {} lamports would receive no tokens", config.amount));
+                }
+                let max_sol_cost = apply_slippage_up(config.amount, config.slippage);
+
+                instructions.push(create_associated_token_account_idempotent(
+                    &owner,
+                    &owner,
+                    &mint,
+                    &spl_token::id(),
+                ));
+                instructions.push(buy_instruction(
+                    &owner,
+                    &mint,
+                    &bonding_curve,
+                    &associated_bonding_curve,
+                    &user_ata,
+                    token_amount,
+                    max_sol_cost,
+                )?);
+            }
+            SwapDirection::Sell => {
+                let sol_output = get_sell_sol_amount(pump_info, config.amount);
+                let min_sol_output = apply_slippage_down(sol_output, config.slippage);
+
+                instructions.push(sell_instruction(
+                    &owner,
+                    &mint,
+                    &bonding_curve,
+                    &associated_bonding_curve,
+                    &user_ata,
+                    config.amount,
+                    min_sol_output,
+                )?);
+            }
+        }
+
+        Ok(instructions)
+    }
+
+    // Leases the durable nonce, or None when no nonce account is configured.
+    pub async fn lease_nonce(&self) -> Option<NonceLease> {
+        match &self.nonce {
+            Some(nonce) => Some(nonce.lease().await),
+            None => None,
+        }
+    }
+
+    // Builds and signs the swap, with the Jito tip transfer appended when a
+    // tip account and amount (lamports) are given, against `nonce` when given
+    // and a recent blockhash otherwise. Also returns the last block height at
+    // which the recent blockhash is still valid.
+    pub async fn build_transaction(
+        &self,
+        pump_info: &PumpInfo,
+        config: &SwapConfig,
+        tip: Option<(Pubkey, u64)>,
+        nonce: Option<Hash>,
+    ) -> Result<(VersionedTransaction, u64)> {
+        let mut instructions = self.build_swap_instructions(pump_info, config)?;
+        if let Some((tip_account, tip_lamports)) = tip {
+            instructions.push(system_instruction::transfer(
+                &self.keypair.pubkey(),
+                &tip_account,
+                tip_lamports,
+            ));
+        }
+        self.sign_transaction(&instructions, nonce).await
+    }
+
+    // Simulates the signed swap (as a bundle when going through Jito) and fails
+    // with a `SimulationFailure` if the Pump program would reject it. Returns
+    // the compute units consumed.
+    pub async fn simulate(&self, transaction: &VersionedTransaction, as_bundle: bool) -> Result<Option<u64>> {
+        if as_bundle {
+            match jito::simulate_bundle(&self.client, std::slice::from_ref(transaction)).await {
+                Ok(simulation) => {
+                    return match simulation.error {
+                        Some(error) => Err(SimulationFailure {
+                            pump_error: PumpError::from_message(&error)
+                                .or_else(|| PumpError::from_logs(&simulation.logs)),
+                            error,
+                            units_consumed: Some(simulation.units_consumed),
+                            logs: decode_simulation_logs(&simulation.logs),
+                        }
+                        .into()),
+                        None => Ok(Some(simulation.units_consumed)),
+                    };
+                }
+                // Plain RPC nodes don't serve simulateBundle; a single-transaction
+                // bundle simulates the same way through simulateTransaction.
+                Err(e) => Logger::new("[PUMPFUN-SWAP]".to_string())
+                    .debug(format!("simulateBundle unavailable, using simulateTransaction: {}", e)),
+            }
+        }
+
+        let simulation = self.client.simulate_transaction(transaction).await?.value;
+        match simulation.err {
+            Some(error) => Err(SimulationFailure {
+                error: error.to_string(),
+                pump_error: PumpError::from_transaction_error(&error),
+                units_consumed: simulation.units_consumed,
+                logs: decode_simulation_logs(&simulation.logs.unwrap_or_default()),
+            }
+            .into()),
+            None => Ok(simulation.units_consumed),
+        }
+    }
+
+    // The returned block height bounds how long the transaction is rebroadcast.
+    // A durable nonce transaction never expires on its own, so the current
+    // blockhash's validity window is used for it as well.
+    async fn sign_transaction(
+        &self,
+        instructions: &[Instruction],
+        nonce: Option<Hash>,
+    ) -> Result<(VersionedTransaction, u64)> {
+        // Only hit the RPC when the background refresher has nothing fresh
+        let (recent_blockhash, last_valid_block_height) = match self.blockhash_cache.get() {
+            Some(cached) => (cached.blockhash, cached.last_valid_block_height),
+            None => {
+                self.client
+                    .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+                    .await?
+            }
+        };
+
+        let transaction = match (&self.nonce, nonce) {
+            (Some(durable_nonce), Some(nonce)) => {
+                // advance_nonce_account must be the first instruction
+                let mut nonce_instructions = vec![system_instruction::advance_nonce_account(
+                    &durable_nonce.account,
+                    &self.keypair.pubkey(),
+                )];
+                nonce_instructions.extend_from_slice(instructions);
+                self.compile_transaction(&nonce_instructions, nonce)?
+            }
+            _ => self.compile_transaction(instructions, recent_blockhash)?,
+        };
+        Ok((transaction, last_valid_block_height))
+    }
+
+    fn compile_transaction(&self, instructions: &[Instruction], blockhash: Hash) -> Result<VersionedTransaction> {
+        let payer = self.keypair.pubkey();
+        let message = match &self.lookup_table {
+            Some(lookup_table) => VersionedMessage::V0(v0::Message::try_compile(
+                &payer,
+                instructions,
+                std::slice::from_ref(lookup_table),
+                blockhash,
+            )?),
+            None => VersionedMessage::Legacy(Message::new_with_blockhash(instructions, Some(&payer), &blockhash)),
+        };
+        Ok(VersionedTransaction::try_new(message, &[self.keypair.as_ref()])?)
+    }
+}
+
+// Accounts every Pump swap touches, for the bot's address lookup table.
+// Invoked programs always stay in the static keys; the table only helps where
+// they are also passed as plain accounts.
+pub fn pump_lookup_table_addresses() -> Result<Vec<Pubkey>> {
+    Ok(vec![
+        Pubkey::from_str(PUMP_GLOBAL)?,
+        Pubkey::from_str(PUMP_FEE_RECIPIENT)?,
+        Pubkey::from_str(PUMP_EVENT_AUTHORITY)?,
+        Pubkey::from_str(PUMP_PROGRAM)?,
+        system_program::id(),
+        compute_budget::id(),
+        spl_token::id(),
+        solana_sdk::sysvar::rent::id(),
+        spl_associated_token_account::id(),
+    ])
+}
+
+pub async fn get_pump_info(
+    rpc_client: Arc<RpcClient>,
+    mint: &str,
+) -> Result<PumpInfo> {
+    let mint_pubkey = Pubkey::from_str(mint)?;
+    let program_id = Pubkey::from_str(PUMP_PROGRAM)?;
+    let (bonding_curve, bonding_curve_account) =
+        get_bonding_curve_account(rpc_client, &mint_pubkey, &program_id).await?;
+
+    Ok(pump_info_from_account(&mint_pubkey, &bonding_curve, &bonding_curve_account))
+}
+
+pub fn pump_info_from_account(mint: &Pubkey, bonding_curve: &Pubkey, account: &BondingCurveAccount) -> PumpInfo {
+    let associated_bonding_curve = get_associated_token_address(bonding_curve, mint);
+
+    PumpInfo {
+        mint: mint.to_string(),
+        bonding_curve: bonding_curve.to_string(),
+        associated_bonding_curve: associated_bonding_curve.to_string(),
+        virtual_sol_reserves: account.virtual_sol_reserves,
+        virtual_token_reserves: account.virtual_token_reserves,
+        real_sol_reserves: account.real_sol_reserves,
+        real_token_reserves: account.real_token_reserves,
+        token_total_supply: account.token_total_supply,
+        complete: account.complete,
+        creator: account.creator.map(|creator| creator.to_string()),
+    }
+}
+
+pub async fn get_bonding_curve_account(
+    rpc_client: Arc<RpcClient>,
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<(Pubkey, BondingCurveAccount)> {
+    let bonding_curve = get_pda(mint, program_id)?;
+
+    let mut last_error = None;
+    for _ in 0..BONDING_CURVE_RETRIES {
+        match rpc_client.get_account_data(&bonding_curve).await {
+            Ok(data) => match BondingCurveAccount::decode(&data) {
+                Ok(account) => return Ok((bonding_curve, account)),
+                Err(e) => last_error = Some(e),
+            },
+            Err(e) => last_error = Some(anyhow!("Failed to get bonding curve account: {}", e)),
+        }
+        tokio::time::sleep(Duration::from_millis(BONDING_CURVE_RETRY_DELAY)).await;
+    }
+
+    Err(last_error.unwrap_or_else(|| anyhow!("Failed to get valid bonding curve account after retries")))
+}
+
+// The curve as a TradeEvent left it. Events only carry the virtual reserves;
+// the real ones follow from the fixed offsets every curve starts with.
+pub fn pump_info_from_trade(trade: &TradeEvent) -> Result<PumpInfo> {
+    let bonding_curve = get_pda(&trade.mint, &Pubkey::from_str(PUMP_PROGRAM)?)?;
+    let real_token_reserves = trade
+        .virtual_token_reserves
+        .saturating_sub(PUMP_INITIAL_VIRTUAL_TOKEN_RESERVES - PUMP_INITIAL_REAL_TOKEN_RESERVES);
+
+    Ok(PumpInfo {
+        mint: trade.mint.to_string(),
+        bonding_curve: bonding_curve.to_string(),
+        associated_bonding_curve: get_associated_token_address(&bonding_curve, &trade.mint).to_string(),
+        virtual_sol_reserves: trade.virtual_sol_reserves,
+        virtual_token_reserves: trade.virtual_token_reserves,
+        real_sol_reserves: trade.virtual_sol_reserves.saturating_sub(PUMP_INITIAL_VIRTUAL_SOL_RESERVES),
+        real_token_reserves,
+        token_total_supply: PUMP_TOKEN_TOTAL_SUPPLY,
+        complete: real_token_reserves == 0,
+        creator: None,
+    })
+}
+
+pub fn get_pda(mint: &Pubkey, program_id: &Pubkey) -> Result<Pubkey> {
+    let seeds = [BONDING_CURVE_SEED, mint.as_ref()];
+    let (bonding_curve, _bump) = Pubkey::find_program_address(&seeds, program_id);
+    Ok(bonding_curve)
+}
+
+// Tokens received for `sol_amount` lamports, after the Pump fee.
+pub fn get_buy_token_amount(pump_info: &PumpInfo, sol_amount: u64) -> u64 {
+    let sol_in = sol_amount as u128 * (10_000 - PUMP_FEE_BPS) as u128 / 10_000;
+    let virtual_sol = pump_info.virtual_sol_reserves as u128;
+    let virtual_token = pump_info.virtual_token_reserves as u128;
+    if virtual_sol + sol_in == 0 {
+        return 0;
+    }
+    let tokens = sol_in * virtual_token / (virtual_sol + sol_in);
+    tokens.min(pump_info.real_token_reserves as u128) as u64
+}
+
+// Lamports received for selling `token_amount`, after the Pump fee.
+pub fn get_sell_sol_amount(pump_info: &PumpInfo, token_amount: u64) -> u64 {
+    let token_in = token_amount as u128;
+    let virtual_sol = pump_info.virtual_sol_reserves as u128;
+    let virtual_token = pump_info.virtual_token_reserves as u128;
+    if virtual_token + token_in == 0 {
+        return 0;
+    }
+    let sol = token_in * virtual_sol / (virtual_token + token_in);
+    (sol * (10_000 - PUMP_FEE_BPS) as u128 / 10_000) as u64
+}
+
+// What a swap built from the `quote` curve does when it executes against the
+// `current` one, enforcing the slippage bounds the Pump program checks: the
+// tokens moved and the lamports paid (buy, fee included) or received (sell).
+pub fn simulate_swap(quote: &PumpInfo, current: &PumpInfo, config: &SwapConfig) -> Result<(u64, u64)> {
+    if quote.complete || current.complete {
+        return Err(PumpError::BondingCurveComplete.into());
+    }
+    match config.swap_direction {
+        SwapDirection::Buy => {
+            // The instruction asks for the quoted tokens and caps what it pays
+            let token_amount = get_buy_token_amount(quote, config.amount);
+            if token_amount == 0 {
+                return Err(anyhow!("Buy of {} lamports would receive no tokens", config.amount));
+            }
+            let cost = get_buy_sol_cost(current, token_amount).ok_or(PumpError::TooMuchSolRequired)?;
+            if cost > apply_slippage_up(config.amount, config.slippage) {
+                return Err(PumpError::TooMuchSolRequired.into());
+            }
+            Ok((token_amount, cost))
+        }
+        SwapDirection::Sell => {
+            let proceeds = get_sell_sol_amount(current, config.amount);
+            if proceeds < apply_slippage_down(get_sell_sol_amount(quote, config.amount), config.slippage) {
+                return Err(PumpError::TooLittleSolReceived.into());
+            }
+            Ok((config.amount, proceeds))
+        }
+    }
+}
+
+// Lamports a buy of exactly `token_amount` costs, Pump fee included. The
+// inverse of `get_buy_token_amount`; `None` if the curve can't fill it.
+pub fn get_buy_sol_cost(pump_info: &PumpInfo, token_amount: u64) -> Option<u64> {
+    let token_out = token_amount as u128;
+    let virtual_sol = pump_info.virtual_sol_reserves as u128;
+    let virtual_token = pump_info.virtual_token_reserves as u128;
+    if token_amount > pump_info.real_token_reserves || token_out >= virtual_token {
+        return None;
+    }
+    let sol_in = (token_out * virtual_sol).div_ceil(virtual_token - token_out);
+    Some((sol_in * 10_000).div_ceil((10_000 - PUMP_FEE_BPS) as u128) as u64)
+}
+
+// Lamports per raw token unit a buy of `sol_amount` would pay, net of the Pump
+// fee so it compares directly with a TradeEvent's price.
+pub fn get_buy_price(pump_info: &PumpInfo, sol_amount: u64) -> Option<f64> {
+    let tokens = get_buy_token_amount(pump_info, sol_amount);
+    if tokens == 0 {
+        return None;
+    }
+    let sol_in = sol_amount as u128 * (10_000 - PUMP_FEE_BPS) as u128 / 10_000;
+    Some(sol_in as f64 / tokens as f64)
+}
+
+// Lamports a swap of `token_amount` moves through the curve, as its TradeEvent
+// reports them: without the Pump fee.
+pub fn get_curve_sol_amount(pump_info: &PumpInfo, is_buy: bool, token_amount: u64) -> u64 {
+    let token = token_amount as u128;
+    let virtual_sol = pump_info.virtual_sol_reserves as u128;
+    let virtual_token = pump_info.virtual_token_reserves as u128;
+    let sol = if is_buy {
+        match virtual_token.checked_sub(token).filter(|rest| *rest > 0) {
+            Some(rest) => (token * virtual_sol).div_ceil(rest),
+            None => 0,
+        }
+    } else {
+        (token * virtual_sol).checked_div(virtual_token + token).unwrap_or_default()
+    };
+    sol as u64
+}
+
+// Curve spot price, in lamports per raw token unit, once a buy of
+// `token_amount` has left the curve.
+pub fn get_price_after_buy(pump_info: &PumpInfo, token_amount: u64) -> Option<f64> {
+    let virtual_token = pump_info
+        .virtual_token_reserves
+        .checked_sub(token_amount)
+        .filter(|virtual_token| *virtual_token > 0)?;
+    let virtual_sol =
+        pump_info.virtual_sol_reserves as u128 * pump_info.virtual_token_reserves as u128 / virtual_token as u128;
+    Some(virtual_sol as f64 / virtual_token as f64)
+}
+
+// Fully diluted market cap in SOL at the current curve price.
+pub fn get_market_cap(pump_info: &PumpInfo) -> f64 {
+    if pump_info.virtual_token_reserves == 0 {
+        return 0.0;
+    }
+    pump_info.virtual_sol_reserves as f64 / pump_info.virtual_token_reserves as f64
+        * pump_info.token_total_supply as f64
+        / 1_000_000_000.0
+}
+
+// Percent of the curve's real token reserves sold so far.
+pub fn get_curve_progress(pump_info: &PumpInfo) -> f64 {
+    let sold = PUMP_INITIAL_REAL_TOKEN_RESERVES.saturating_sub(pump_info.real_token_reserves);
+    sold as f64 * 100.0 / PUMP_INITIAL_REAL_TOKEN_RESERVES as f64
+}
+
+// Largest buy in lamports whose average price, as in `get_buy_price`, stays
+// at or below `max_price`. On the constant-product curve that average is
+// (virtual_sol + sol_in) / virtual_token.
+pub fn get_max_buy_amount(pump_info: &PumpInfo, max_price: f64) -> u64 {
+    let max_sol_in = max_price * pump_info.virtual_token_reserves as f64 - pump_info.virtual_sol_reserves as f64;
+    if max_sol_in <= 0.0 {
+        return 0;
+    }
+    (max_sol_in * 10_000.0 / (10_000 - PUMP_FEE_BPS) as f64) as u64
+}
+
+// Decodes one "Program data:" payload, returning `None` for any other event.
+pub fn decode_trade_event(program_data: &str) -> Option<TradeEvent> {
+    let data = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, program_data).ok()?;
+    decode_trade_event_data(&data)
+}
+
+// Decodes the data of Pump's event self-CPI, which carries the same event
+// as the logs and is kept when they are truncated.
+pub fn decode_trade_event_instruction(data: &[u8]) -> Option<TradeEvent> {
+    decode_trade_event_data(data.strip_prefix(&EVENT_IX_TAG[..])?)
+}
+
+fn decode_trade_event_data(data: &[u8]) -> Option<TradeEvent> {
+    if data.len() < 8 || data[..8] != PUMP_TRADE_EVENT {
+        return None;
+    }
+    // Read as a prefix; newer program versions append fields to the event
+    TradeEvent::deserialize(&mut &data[8..]).ok()
+}
+
+// Keeps the program log and failure lines, dropping invoke/success noise.
+fn decode_simulation_logs(logs: &[String]) -> Vec<String> {
+    logs.iter()
+        .filter(|log| {
+            log.starts_with("Program log:") || log.contains("failed") || log.contains("Error")
+        })
+        .cloned()
+        .collect()
+}
+
+fn apply_slippage_up(amount: u64, slippage: u64) -> u64 {
+    (amount as u128 * (100 + slippage) as u128 / 100) as u64
+}
+
+fn apply_slippage_down(amount: u64, slippage: u64) -> u64 {
+    (amount as u128 * 100u128.saturating_sub(slippage as u128) / 100) as u64
+}
+
+fn compute_budget_instructions() -> Vec<Instruction> {
+    let unit_price = import_optional_env_var("UNIT_PRICE")
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_UNIT_PRICE);
+    let unit_limit = import_optional_env_var("UNIT_LIMIT")
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(DEFAULT_UNIT_LIMIT);
+
+    vec![
+        ComputeBudgetInstruction::set_compute_unit_price(unit_price),
+        ComputeBudgetInstruction::set_compute_unit_limit(unit_limit),
+    ]
+}
+
+fn buy_instruction(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    bonding_curve: &Pubkey,
+    associated_bonding_curve: &Pubkey,
+    user_ata: &Pubkey,
+    token_amount: u64,
+    max_sol_cost: u64,
+) -> Result<Instruction> {
+    let mut data = PUMP_BUY_METHOD.to_vec();
+    data.extend_from_slice(&token_amount.to_le_bytes());
+    data.extend_from_slice(&max_sol_cost.to_le_bytes());
+
+    Ok(Instruction {
+        program_id: Pubkey::from_str(PUMP_PROGRAM)?,
+        accounts: vec![
+            AccountMeta::new_readonly(Pubkey::from_str(PUMP_GLOBAL)?, false),
+            AccountMeta::new(Pubkey::from_str(PUMP_FEE_RECIPIENT)?, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*bonding_curve, false),
+            AccountMeta::new(*associated_bonding_curve, false),
+            AccountMeta::new(*user_ata, false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::rent::id(), false),
+            AccountMeta::new_readonly(Pubkey::from_str(PUMP_EVENT_AUTHORITY)?, false),
+            AccountMeta::new_readonly(Pubkey::from_str(PUMP_PROGRAM)?, false),
+        ],
+        data,
+    })
+}
+
+fn sell_instruction(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    bonding_curve: &Pubkey,
+    associated_bonding_curve: &Pubkey,
+    user_ata: &Pubkey,
+    token_amount: u64,
+    min_sol_output: u64,
+) -> Result<Instruction> {
+    let mut data = PUMP_SELL_METHOD.to_vec();
+    data.extend_from_slice(&token_amount.to_le_bytes());
+    data.extend_from_slice(&min_sol_output.to_le_bytes());
+
+    Ok(Instruction {
+        program_id: Pubkey::from_str(PUMP_PROGRAM)?,
+        accounts: vec![
+            AccountMeta::new_readonly(Pubkey::from_str(PUMP_GLOBAL)?, false),
+            AccountMeta::new(Pubkey::from_str(PUMP_FEE_RECIPIENT)?, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*bonding_curve, false),
+            AccountMeta::new(*associated_bonding_curve, false),
+            AccountMeta::new(*user_ata, false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(Pubkey::from_str(PUMP_EVENT_AUTHORITY)?, false),
+            AccountMeta::new_readonly(Pubkey::from_str(PUMP_PROGRAM)?, false),
+        ],
+        data,
+    })
+}
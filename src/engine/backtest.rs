@@ -0,0 +1,794 @@
+use {
+    crate::{
+        common::utils::{import_optional_env_var, SwapConfig, SwapDirection},
+        dex::pump_fun::{get_buy_price, get_curve_sol_amount, get_price_after_buy, get_sell_sol_amount, pump_info_from_trade, simulate_swap, PumpInfo, TradeEvent},
+        engine::{
+            executor::{pump_error, SwapResult},
+            exit::{ExitPosition, ExitReason, ExitRules},
+            filters::EntryFilters,
+            front_run::{FrontRunDecision, FrontRunGuard},
+            journal::{Decision, JournalRecord, JournalTrade},
+            paper::{paper_balance, paper_fee, TOKEN_ACCOUNT_RENT},
+            positions::{sol_amounts, Fill, Position},
+            report::PnlReport,
+            sizing::{copy_buy_amount, copy_sell_amount},
+            slippage::SlippageRetryPolicy,
+            staleness::StalenessFilter,
+        },
+        services::jito::{self, TipConfig},
+    },
+    anyhow::{anyhow, Result},
+    chrono::{DateTime, Utc},
+    serde::Deserialize,
+    std::{
+        collections::BTreeMap,
+        fs::File,
+        io::{BufRead, BufReader},
+        path::Path,
+        time::{Duration, Instant},
+    },
+};
+
+const DEFAULT_DELAY_SLOTS: u64 = 2;
+const DEFAULT_DELAY_MS: u64 = 1_000;
+const SLOT_MS: i64 = 400; // milliseconds
+const BACKTEST_PATH: &str = "backtest";
+
+// A target trade from a fixture file: a journal trade plus the slot it landed
+// in, when known.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoricalTrade {
+    #[serde(default)]
+    pub slot: Option<u64>,
+    #[serde(flatten)]
+    pub trade: JournalTrade,
+}
+
+// How long after a target trade our swap executes. Slots are used when every
+// trade has one, milliseconds from the trade timestamps otherwise.
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyModel {
+    pub delay_slots: u64,
+    pub delay_ms: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    pub balance: u64,
+    pub fee: u64,
+    // Jito tip charged per swap, as paper trading charges it
+    pub tip: u64,
+    pub slippage: u64,
+    pub latency: LatencyModel,
+    // Trades by this wallet are signals; every other trade only moves the
+    // curve. Without one, every trade is a signal.
+    pub target: Option<String>,
+    pub filters: EntryFilters,
+    pub exit_rules: ExitRules,
+    pub front_run: Option<FrontRunGuard>,
+    pub staleness: Option<StalenessFilter>,
+    pub retry_policy: Option<SlippageRetryPolicy>,
+}
+
+impl BacktestConfig {
+    // The live strategy settings, plus `PAPER_BALANCE` / `PAPER_TX_FEE` and the
+    // `JITO_TIP_*` tip for the simulated wallet and `BACKTEST_DELAY_SLOTS` /
+    // `BACKTEST_DELAY_MS`. No tip stream runs offline, so the tip is
+    // `JITO_TIP_VALUE` capped at `JITO_TIP_MAX`.
+    pub fn from_env() -> Result<Self> {
+        let delay_slots = import_optional_env_var("BACKTEST_DELAY_SLOTS")
+            .map(|v| v.parse::<u64>().map_err(|e| anyhow!("Invalid BACKTEST_DELAY_SLOTS: {}", e)))
+            .transpose()?
+            .unwrap_or(DEFAULT_DELAY_SLOTS);
+        let delay_ms = import_optional_env_var("BACKTEST_DELAY_MS")
+            .map(|v| v.parse::<u64>().map_err(|e| anyhow!("Invalid BACKTEST_DELAY_MS: {}", e)))
+            .transpose()?
+            .unwrap_or(DEFAULT_DELAY_MS);
+
+        Ok(Self {
+            balance: paper_balance()?,
+            fee: paper_fee()?,
+            tip: (jito::get_tip_value(&TipConfig::from_env()?)? * 1_000_000_000.0) as u64,
+            slippage: import_optional_env_var("SLIPPAGE")
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(5),
+            latency: LatencyModel { delay_slots, delay_ms },
+            target: None,
+            filters: EntryFilters::from_env()?,
+            exit_rules: ExitRules::from_env()?,
+            front_run: FrontRunGuard::from_env()?,
+            staleness: StalenessFilter::from_env()?,
+            retry_policy: SlippageRetryPolicy::from_env()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EquityPoint {
+    pub timestamp: i64,
+    pub slot: Option<u64>,
+    pub cash: u64,
+    // Cash plus what selling every holding would return
+    pub equity: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BacktestResult {
+    pub starting_balance: u64,
+    pub trades: usize,
+    pub signals: usize,
+    // What the bot would have journaled, on the replayed clock
+    pub records: Vec<JournalRecord>,
+    pub report: PnlReport,
+    pub equity_curve: Vec<EquityPoint>,
+    pub max_drawdown: f64,
+}
+
+impl BacktestResult {
+    pub fn final_equity(&self) -> u64 {
+        self.equity_curve.last().map(|point| point.equity).unwrap_or(self.starting_balance)
+    }
+
+    pub fn return_pct(&self) -> f64 {
+        (self.final_equity() as f64 / self.starting_balance.max(1) as f64 - 1.0) * 100.0
+    }
+
+    pub fn equity_csv(&self) -> String {
+        let mut csv = String::from("timestamp,slot,cash_sol,equity_sol\n");
+        for point in &self.equity_curve {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                point.timestamp,
+                point.slot.map(|slot| slot.to_string()).unwrap_or_default(),
+                point.cash as f64 / 1_000_000_000.0,
+                point.equity as f64 / 1_000_000_000.0
+            ));
+        }
+        csv
+    }
+}
+
+// Target trades from a trade journal (the copy signals it recorded) or a
+// fixture file of `HistoricalTrade` lines. Lines that are neither are skipped.
+pub fn load_trades(path: impl AsRef<Path>) -> Result<Vec<HistoricalTrade>> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?;
+
+    let mut trades = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if let Ok(record) = serde_json::from_str::<JournalRecord>(&line) {
+            if let Some(trade) = record.trade.filter(|_| record.source == "copy") {
+                trades.push(HistoricalTrade {
+                    slot: record.target_slot,
+                    trade,
+                });
+            }
+        } else if let Ok(trade) = serde_json::from_str::<HistoricalTrade>(&line) {
+            trades.push(trade);
+        }
+    }
+    Ok(trades)
+}
+
+// Replays `trades` in order through the live sizing, filters, front-run guard,
+// slippage retries and exit rules, filling every swap on the bonding curve as
+// it stood once the modelled latency had passed. Our own fills don't move the
+// replayed curve.
+pub fn run_backtest(trades: &[HistoricalTrade], config: &BacktestConfig) -> Result<BacktestResult> {
+    let by_slot = !trades.is_empty() && trades.iter().all(|trade| trade.slot.is_some());
+    let mut events = trades
+        .iter()
+        .map(|historical| {
+            let trade = TradeEvent::try_from(&historical.trade)?;
+            let curve = pump_info_from_trade(&trade)?;
+            let key = match (by_slot, historical.slot) {
+                (true, Some(slot)) => slot as i64,
+                _ => trade.timestamp * 1000,
+            };
+            Ok(Event {
+                key,
+                slot: historical.slot,
+                timestamp_ms: trade.timestamp * 1000,
+                trade,
+                curve,
+            })
+        })
+        .collect::<Result<Vec<Event>>>()?;
+    events.sort_by_key(|event| event.key);
+
+    let mut backtest = Backtest {
+        config,
+        by_slot,
+        started: Instant::now(),
+        start_ms: events.first().map(|event| event.timestamp_ms).unwrap_or_default(),
+        curves: BTreeMap::new(),
+        cash: config.balance,
+        positions: BTreeMap::new(),
+        exits: BTreeMap::new(),
+        pending: Vec::new(),
+        records: Vec::new(),
+        equity_curve: Vec::new(),
+        peak_equity: config.balance,
+        max_drawdown: 0.0,
+        fills: 0,
+    };
+
+    let mut signals = 0;
+    for event in &events {
+        backtest.execute_due(Some(event.key));
+        backtest.curves.insert(event.curve.mint.clone(), event.curve.clone());
+        let is_signal = config
+            .target
+            .as_ref()
+            .is_none_or(|target| *target == event.trade.user.to_string());
+        if is_signal {
+            signals += 1;
+            backtest.signal(event);
+        }
+        backtest.check_exits(event);
+        backtest.record_equity(event.timestamp_ms, event.slot);
+    }
+    // Swaps still in flight when the history ends
+    if let Some(last) = events.last().filter(|_| !backtest.pending.is_empty()) {
+        backtest.execute_due(None);
+        backtest.record_equity(last.timestamp_ms, last.slot);
+    }
+
+    Ok(BacktestResult {
+        starting_balance: config.balance,
+        trades: events.len(),
+        signals,
+        report: PnlReport::from_records(&backtest.records),
+        records: backtest.records,
+        equity_curve: backtest.equity_curve,
+        max_drawdown: backtest.max_drawdown,
+    })
+}
+
+struct Event {
+    key: i64,
+    slot: Option<u64>,
+    timestamp_ms: i64,
+    trade: TradeEvent,
+    curve: PumpInfo,
+}
+
+// A swap decided on but not yet executed.
+struct Pending {
+    due: i64,
+    due_ms: i64,
+    due_slot: Option<u64>,
+    is_buy: bool,
+    amount: u64,
+    quote: PumpInfo,
+    slippage: u64,
+    target_trade: Option<TradeEvent>,
+    exit_reason: Option<ExitReason>,
+    record: JournalRecord,
+}
+
+struct Backtest<'a> {
+    config: &'a BacktestConfig,
+    by_slot: bool,
+    // Replayed time maps onto `Instant`s from here for the exit rules
+    started: Instant,
+    start_ms: i64,
+    curves: BTreeMap<String, PumpInfo>,
+    cash: u64,
+    positions: BTreeMap<String, Position>,
+    exits: BTreeMap<String, ExitPosition>,
+    pending: Vec<Pending>,
+    records: Vec<JournalRecord>,
+    equity_curve: Vec<EquityPoint>,
+    peak_equity: u64,
+    max_drawdown: f64,
+    fills: u64,
+}
+
+impl Backtest<'_> {
+    fn instant(&self, timestamp_ms: i64) -> Instant {
+        self.started + Duration::from_millis((timestamp_ms - self.start_ms).max(0) as u64)
+    }
+
+    fn held(&self, mint: &str) -> u64 {
+        self.positions.get(mint).map(|position| position.token_amount).unwrap_or_default()
+    }
+
+    // When a swap decided at `key` / `timestamp_ms` executes.
+    fn schedule(&self, key: i64, timestamp_ms: i64, slot: Option<u64>) -> (i64, i64, Option<u64>) {
+        let latency = self.config.latency;
+        if self.by_slot {
+            (
+                key + latency.delay_slots as i64,
+                timestamp_ms + latency.delay_slots as i64 * SLOT_MS,
+                slot.map(|slot| slot + latency.delay_slots),
+            )
+        } else {
+            (key + latency.delay_ms as i64, timestamp_ms + latency.delay_ms as i64, None)
+        }
+    }
+
+    fn signal(&mut self, event: &Event) {
+        let trade = &event.trade;
+        let mint = event.curve.mint.clone();
+        let mut record = JournalRecord::new("copy", &mint, trade.is_buy);
+        record.timestamp = format_timestamp(event.timestamp_ms);
+        record.target_wallet = Some(trade.user.to_string());
+        record.target_slot = event.slot;
+        record.trade = Some(JournalTrade::from(trade));
+
+        if !trade.is_buy {
+            let now = self.instant(event.timestamp_ms);
+            if let Some(exit) = self.exits.get_mut(&mint) {
+                exit.last_target_sell = Some(now);
+            }
+        }
+
+        let (due, due_ms, due_slot) = self.schedule(event.key, event.timestamp_ms, event.slot);
+        if trade.is_buy {
+            if let Some(stale) = self.config.staleness.as_ref().and_then(|filter| {
+                filter.check_at(event.slot.unwrap_or_default(), Some(trade.timestamp), due_slot, due_ms)
+            }) {
+                self.records.push(record.skipped("stale_signal", stale));
+                return;
+            }
+            if let Some(rejection) = self.config.filters.check_local(&event.curve) {
+                self.records.push(record.skipped(rejection.code(), rejection));
+                return;
+            }
+        }
+
+        let amount = if trade.is_buy {
+            let amount = copy_buy_amount(&event.curve);
+            match self.config.front_run.as_ref().map(|guard| guard.check(&event.curve, trade, amount)) {
+                Some(FrontRunDecision::Skip) => {
+                    self.records.push(record.skipped("front_run", "quote too far over target fill"));
+                    return;
+                }
+                Some(FrontRunDecision::Downsize(downsized)) => downsized,
+                _ => amount,
+            }
+        } else {
+            let amount = copy_sell_amount(self.held(&mint));
+            if amount == 0 {
+                self.records.push(record.skipped("no_tokens", "No tokens available to sell"));
+                return;
+            }
+            amount
+        };
+
+        self.pending.push(Pending {
+            due,
+            due_ms,
+            due_slot,
+            is_buy: trade.is_buy,
+            amount,
+            quote: event.curve.clone(),
+            slippage: self.config.slippage,
+            target_trade: Some(trade.clone()),
+            exit_reason: None,
+            record,
+        });
+    }
+
+    fn check_exits(&mut self, event: &Event) {
+        if self.config.exit_rules.is_empty() {
+            return;
+        }
+        let now = self.instant(event.timestamp_ms);
+        let mut triggers = Vec::new();
+        for (mint, exit) in self.exits.iter_mut().filter(|(_, exit)| !exit.selling) {
+            let Some(curve) = self.curves.get(mint).filter(|curve| curve.virtual_token_reserves > 0) else {
+                continue;
+            };
+            let price = curve.virtual_sol_reserves as f64 / curve.virtual_token_reserves as f64;
+            if let Some((reason, percent)) = exit.evaluate_at(price, now) {
+                exit.selling = true;
+                triggers.push((mint.clone(), curve.clone(), reason, percent));
+            }
+        }
+
+        for (mint, curve, reason, percent) in triggers {
+            let held = self.held(&mint);
+            let (due, due_ms, due_slot) = self.schedule(event.key, event.timestamp_ms, event.slot);
+            let mut record = JournalRecord::new("exit", &mint, false);
+            record.timestamp = format_timestamp(event.timestamp_ms);
+            record.exit_reason = Some(reason.to_string());
+            self.pending.push(Pending {
+                due,
+                due_ms,
+                due_slot,
+                is_buy: false,
+                amount: (held as u128 * percent as u128 / 100) as u64,
+                quote: curve,
+                slippage: self.config.slippage,
+                target_trade: None,
+                exit_reason: Some(reason),
+                record,
+            });
+        }
+    }
+
+    // Executes every pending swap due before `key`, or all of them.
+    fn execute_due(&mut self, key: Option<i64>) {
+        loop {
+            let next = self
+                .pending
+                .iter()
+                .enumerate()
+                .filter(|(_, pending)| key.is_none_or(|key| pending.due < key))
+                .min_by_key(|(_, pending)| pending.due)
+                .map(|(index, _)| index);
+            let Some(index) = next else { break };
+            let pending = self.pending.remove(index);
+            self.execute(pending);
+        }
+    }
+
+    fn execute(&mut self, mut pending: Pending) {
+        let mint = pending.quote.mint.clone();
+        let current = self.curves.get(&mint).cloned().unwrap_or_else(|| pending.quote.clone());
+        let now = self.instant(pending.due_ms);
+        let held = self.held(&mint);
+        let config = SwapConfig {
+            slippage: pending.slippage,
+            use_jito: self.config.tip > 0,
+            amount: if pending.is_buy { pending.amount } else { pending.amount.min(held) },
+            swap_direction: if pending.is_buy { SwapDirection::Buy } else { SwapDirection::Sell },
+        };
+        let mut record = pending.record.clone();
+
+        let filled = if config.amount == 0 {
+            Err(anyhow!("No tokens available to sell"))
+        } else {
+            simulate_swap(&pending.quote, &current, &config).and_then(|(token_amount, sol_amount)| {
+                let (sol_change, cost) = if pending.is_buy {
+                    let rent = if held == 0 { TOKEN_ACCOUNT_RENT } else { 0 };
+                    let total = sol_amount + self.config.fee + self.config.tip + rent;
+                    (-(total as i64), total)
+                } else {
+                    (sol_amount as i64 - (self.config.fee + self.config.tip) as i64, 0)
+                };
+                if cost > self.cash {
+                    return Err(anyhow!(
+                        "Balance of {} SOL can't cover {} SOL",
+                        self.cash as f64 / 1_000_000_000.0,
+                        cost as f64 / 1_000_000_000.0
+                    ));
+                }
+                Ok((token_amount, sol_change))
+            })
+        };
+
+        match filled {
+            Ok((token_amount, sol_change)) => {
+                self.fills += 1;
+                let (sol_amount, excess_cost) = sol_amounts(pending.is_buy, sol_change);
+                let fill = Fill {
+                    signature: format!("{}-{}", BACKTEST_PATH, self.fills),
+                    mint: mint.clone(),
+                    is_buy: pending.is_buy,
+                    slot: pending.due_slot.unwrap_or_default(),
+                    token_amount,
+                    sol_amount,
+                    excess_cost,
+                    fee: self.config.fee,
+                    curve_sol_amount: Some(get_curve_sol_amount(&current, pending.is_buy, token_amount)),
+                };
+                if pending.is_buy {
+                    self.cash -= sol_amount;
+                } else {
+                    self.cash = (self.cash + sol_amount).saturating_sub(excess_cost);
+                }
+                let position = self.positions.entry(mint.clone()).or_insert_with(|| Position {
+                    mint: mint.clone(),
+                    ..Position::default()
+                });
+                position.apply(&fill);
+                let remaining = position.token_amount;
+                let entry_price = get_price_after_buy(&current, token_amount);
+
+                record.decision = Decision::Copied;
+                record.signature = Some(fill.signature.clone());
+                record.send_path = Some(BACKTEST_PATH.to_string());
+                record.landed_slot = pending.due_slot;
+                record.slot_delta = pending
+                    .due_slot
+                    .zip(record.target_slot)
+                    .map(|(slot, target_slot)| slot as i64 - target_slot as i64);
+                record.fill = Some(fill);
+
+                if remaining == 0 {
+                    self.exits.remove(&mint);
+                } else if pending.is_buy && !self.config.exit_rules.is_empty() {
+                    let exit = self
+                        .exits
+                        .entry(mint.clone())
+                        .or_insert_with(|| ExitPosition::new(&current, &self.config.exit_rules, now));
+                    if let Some(entry_price) = entry_price {
+                        exit.add_entry(entry_price, token_amount);
+                    }
+                } else if let (Some(reason), Some(exit)) = (pending.exit_reason, self.exits.get_mut(&mint)) {
+                    exit.finish_sell(true, reason, now);
+                }
+            }
+            Err(e) => {
+                // Only a buy that hit the slippage bound is worth retrying
+                if pending.is_buy && pump_error(&e).is_some_and(|error| error.is_slippage()) {
+                    if let Some(policy) = &self.config.retry_policy {
+                        let quote_price = get_buy_price(&current, pending.amount);
+                        let target_price = pending.target_trade.as_ref().and_then(|trade| trade.price());
+                        let within = matches!(
+                            (quote_price, target_price),
+                            (Some(quote), Some(target)) if policy.within_premium(quote, target)
+                        );
+                        if let Some(slippage) = policy.next_slippage(pending.slippage).filter(|_| within) {
+                            let (due, due_ms, due_slot) = self.schedule(pending.due, pending.due_ms, pending.due_slot);
+                            pending.due = due;
+                            pending.due_ms = due_ms;
+                            pending.due_slot = due_slot;
+                            pending.slippage = slippage;
+                            pending.quote = current;
+                            self.pending.push(pending);
+                            return;
+                        }
+                    }
+                }
+
+                let result: Result<SwapResult> = Err(e);
+                record = record.with_swap(&result);
+                if let (Some(reason), Some(exit)) = (pending.exit_reason, self.exits.get_mut(&mint)) {
+                    exit.finish_sell(false, reason, now);
+                }
+            }
+        }
+
+        record.timestamp = format_timestamp(pending.due_ms);
+        self.records.push(record);
+    }
+
+    fn record_equity(&mut self, timestamp_ms: i64, slot: Option<u64>) {
+        let holdings: u64 = self
+            .positions
+            .iter()
+            .filter(|(_, position)| position.token_amount > 0)
+            .filter_map(|(mint, position)| {
+                self.curves
+                    .get(mint)
+                    .map(|curve| get_sell_sol_amount(curve, position.token_amount))
+            })
+            .sum();
+        let equity = self.cash + holdings;
+
+        self.peak_equity = self.peak_equity.max(equity);
+        let drawdown = (self.peak_equity - equity) as f64 * 100.0 / self.peak_equity.max(1) as f64;
+        self.max_drawdown = self.max_drawdown.max(drawdown);
+        self.equity_curve.push(EquityPoint {
+            timestamp: timestamp_ms / 1000,
+            slot,
+            cash: self.cash,
+            equity,
+        });
+    }
+}
+
+fn format_timestamp(timestamp_ms: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(timestamp_ms)
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::dex::pump_fun::{PUMP_FEE_BPS, PUMP_INITIAL_VIRTUAL_SOL_RESERVES, PUMP_INITIAL_VIRTUAL_TOKEN_RESERVES},
+        solana_sdk::pubkey::Pubkey,
+    };
+
+    const SOL: u64 = 1_000_000_000;
+
+    // Target and bystander trades on one fresh curve, as the program would
+    // have emitted them.
+    struct History {
+        mint: Pubkey,
+        virtual_sol_reserves: u64,
+        virtual_token_reserves: u64,
+        trades: Vec<HistoricalTrade>,
+    }
+
+    impl History {
+        fn new() -> Self {
+            Self {
+                mint: Pubkey::new_unique(),
+                virtual_sol_reserves: PUMP_INITIAL_VIRTUAL_SOL_RESERVES,
+                virtual_token_reserves: PUMP_INITIAL_VIRTUAL_TOKEN_RESERVES,
+                trades: Vec::new(),
+            }
+        }
+
+        fn buy(mut self, user: &Pubkey, slot: u64, sol_amount: u64) -> Self {
+            let sol_in = sol_amount as u128 * (10_000 - PUMP_FEE_BPS) as u128 / 10_000;
+            let (sol, token) = (self.virtual_sol_reserves as u128, self.virtual_token_reserves as u128);
+            let token_amount = (token - sol * token / (sol + sol_in)) as u64;
+            self.virtual_sol_reserves += sol_in as u64;
+            self.virtual_token_reserves -= token_amount;
+            self.push(user, slot, true, sol_amount, token_amount)
+        }
+
+        fn sell(mut self, user: &Pubkey, slot: u64, token_amount: u64) -> Self {
+            let (sol, token) = (self.virtual_sol_reserves as u128, self.virtual_token_reserves as u128);
+            let sol_amount = (sol - sol * token / (token + token_amount as u128)) as u64;
+            self.virtual_sol_reserves -= sol_amount;
+            self.virtual_token_reserves += token_amount;
+            self.push(user, slot, false, sol_amount, token_amount)
+        }
+
+        fn push(mut self, user: &Pubkey, slot: u64, is_buy: bool, sol_amount: u64, token_amount: u64) -> Self {
+            self.trades.push(HistoricalTrade {
+                slot: Some(slot),
+                trade: JournalTrade {
+                    mint: self.mint.to_string(),
+                    user: user.to_string(),
+                    sol_amount,
+                    token_amount,
+                    is_buy,
+                    timestamp: 1_700_000_000 + (slot as i64 * SLOT_MS) / 1000,
+                    virtual_sol_reserves: self.virtual_sol_reserves,
+                    virtual_token_reserves: self.virtual_token_reserves,
+                },
+            });
+            self
+        }
+    }
+
+    fn config(target: &Pubkey) -> BacktestConfig {
+        BacktestConfig {
+            balance: 100 * SOL,
+            fee: 5_000,
+            tip: 0,
+            slippage: 5,
+            latency: LatencyModel {
+                delay_slots: 2,
+                delay_ms: 1_000,
+            },
+            target: Some(target.to_string()),
+            filters: EntryFilters::default(),
+            exit_rules: ExitRules::default(),
+            front_run: None,
+            staleness: None,
+            retry_policy: None,
+        }
+    }
+
+    fn decisions(result: &BacktestResult) -> Vec<(&str, bool, Decision)> {
+        result
+            .records
+            .iter()
+            .map(|record| (record.source.as_str(), record.is_buy, record.decision))
+            .collect()
+    }
+
+    #[test]
+    fn copies_target_round_trip_after_the_modelled_latency() {
+        let (target, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let history = History::new()
+            .buy(&target, 100, SOL)
+            .buy(&other, 105, 20 * SOL)
+            .sell(&target, 110, 1_000_000_000)
+            .buy(&other, 120, SOL);
+        let result = run_backtest(&history.trades, &config(&target)).unwrap();
+
+        assert_eq!((result.trades, result.signals), (4, 2));
+        assert_eq!(
+            decisions(&result),
+            vec![("copy", true, Decision::Copied), ("copy", false, Decision::Copied)]
+        );
+        let buy = result.records[0].fill.as_ref().unwrap();
+        let sell = result.records[1].fill.as_ref().unwrap();
+        assert_eq!((buy.slot, sell.slot), (102, 112));
+        assert_eq!(result.records[0].slot_delta, Some(2));
+        assert_eq!(sell.token_amount, buy.token_amount / 2);
+
+        // The bystander pumped the curve between our buy and the sell
+        assert!(result.report.total.realized_pnl > 0);
+        assert_eq!(
+            result.report.positions[&history.mint.to_string()].token_amount,
+            buy.token_amount - sell.token_amount
+        );
+        assert_eq!(result.equity_curve.len(), 4);
+        assert!(result.final_equity() > result.starting_balance);
+        assert!(result.return_pct() > 0.0);
+    }
+
+    #[test]
+    fn every_fill_pays_the_jito_tip() {
+        let (target, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let history = History::new()
+            .buy(&target, 100, SOL)
+            .buy(&other, 105, 20 * SOL)
+            .sell(&target, 110, 1_000_000_000);
+        let mut config = config(&target);
+        let untipped = run_backtest(&history.trades, &config).unwrap();
+        config.tip = 1_000_000;
+        let tipped = run_backtest(&history.trades, &config).unwrap();
+
+        let fills = |result: &BacktestResult| -> Vec<Fill> {
+            result.records.iter().filter_map(|record| record.fill.clone()).collect()
+        };
+        let (untipped_fills, tipped_fills) = (fills(&untipped), fills(&tipped));
+        assert_eq!(tipped_fills.len(), 2);
+        assert_eq!(tipped_fills[0].sol_amount, untipped_fills[0].sol_amount + config.tip);
+        assert_eq!(tipped_fills[1].sol_amount, untipped_fills[1].sol_amount - config.tip);
+        assert_eq!(tipped.report.total.fees, untipped.report.total.fees + 2 * config.tip);
+    }
+
+    #[test]
+    fn filtered_buys_leave_nothing_to_sell() {
+        let target = Pubkey::new_unique();
+        let history = History::new().buy(&target, 100, SOL).sell(&target, 110, 1_000_000_000);
+        let mut config = config(&target);
+        config.filters.max_market_cap = Some(10.0);
+        let result = run_backtest(&history.trades, &config).unwrap();
+
+        assert_eq!(
+            decisions(&result),
+            vec![("copy", true, Decision::Skipped), ("copy", false, Decision::Skipped)]
+        );
+        assert_eq!(result.records[0].skip_reason.as_deref(), Some("market_cap_too_high"));
+        assert_eq!(result.records[1].skip_reason.as_deref(), Some("no_tokens"));
+        assert_eq!(result.final_equity(), result.starting_balance);
+        assert_eq!(result.max_drawdown, 0.0);
+    }
+
+    #[test]
+    fn slippage_failures_retry_only_with_a_policy() {
+        let (target, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // A bystander moves the curve before our buy lands
+        let history = History::new()
+            .buy(&target, 100, SOL)
+            .buy(&other, 101, SOL / 2)
+            .buy(&other, 110, SOL / 100);
+        let mut config = config(&target);
+        config.slippage = 1;
+
+        let result = run_backtest(&history.trades, &config).unwrap();
+        // Rejected by the program's slippage check, so nothing was spent
+        assert_eq!(decisions(&result), vec![("copy", true, Decision::Skipped)]);
+        assert_eq!(result.records[0].skip_reason.as_deref(), Some("too_much_sol_required"));
+        assert_eq!(result.final_equity(), result.starting_balance);
+
+        config.retry_policy = Some(SlippageRetryPolicy {
+            step: 5,
+            max_slippage: 20,
+            max_price_premium: 100,
+        });
+        let result = run_backtest(&history.trades, &config).unwrap();
+        assert_eq!(decisions(&result), vec![("copy", true, Decision::Copied)]);
+        // Retried one latency period after the first attempt
+        assert_eq!(result.records[0].fill.as_ref().unwrap().slot, 104);
+    }
+
+    #[test]
+    fn exit_rules_sell_on_the_replayed_curve() {
+        let (target, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let history = History::new()
+            .buy(&target, 100, SOL)
+            .buy(&other, 105, SOL)
+            .buy(&other, 110, 40 * SOL)
+            .buy(&other, 120, SOL);
+        let mut config = config(&target);
+        config.exit_rules.take_profit = vec![(1.5, 100)];
+        let result = run_backtest(&history.trades, &config).unwrap();
+
+        assert_eq!(
+            decisions(&result),
+            vec![("copy", true, Decision::Copied), ("exit", false, Decision::Copied)]
+        );
+        assert_eq!(result.records[1].exit_reason.as_deref(), Some("take profit at 1.5x"));
+        assert_eq!(result.records[1].fill.as_ref().unwrap().slot, 112);
+        assert!(result.report.positions.is_empty());
+        assert!(result.report.total.realized_pnl > 0);
+    }
+}
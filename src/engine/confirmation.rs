@@ -0,0 +1,199 @@
+use {
+    crate::common::{logger::Logger, utils::import_optional_env_var},
+    anyhow::Result,
+    futures::{future::Future, stream::{select_all, BoxStream}, StreamExt},
+    solana_client::{
+        nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+        rpc_config::RpcSignatureSubscribeConfig,
+        rpc_response::RpcSignatureResult,
+    },
+    solana_sdk::{commitment_config::CommitmentConfig, signature::Signature, transaction::TransactionError},
+    std::time::{Duration, Instant},
+    tokio::time,
+};
+
+const DEFAULT_REBROADCAST_INTERVAL: u64 = 500; // milliseconds
+const SUBSCRIBED_POLL_INTERVAL: u64 = 2000; // milliseconds
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfirmationStatus {
+    // `slot_delta` is how many slots after the target's transaction we landed
+    Confirmed { signature: Signature, slot: u64, slot_delta: Option<i64> },
+    Failed { signature: Signature, slot: u64, error: TransactionError },
+    Expired,
+}
+
+// Waits for any of `signatures` to confirm, calling `rebroadcast` every
+// `REBROADCAST_INTERVAL_MS` until then or until the block height passes
+// `last_valid_block_height`. Uses signatureSubscribe when `ws_url` is given and
+// reachable, and polls getSignatureStatuses either way as a fallback.
+// Durable nonce transactions outlive the blockhash, so for them `invalidate`
+// must consume the nonce before they are given up on; until it succeeds
+// tracking goes on.
+pub async fn track_confirmation<F, Fut, I, IFut>(
+    client: &RpcClient,
+    ws_url: Option<&str>,
+    signatures: &[Signature],
+    last_valid_block_height: u64,
+    target_slot: Option<u64>,
+    mut rebroadcast: F,
+    mut invalidate: Option<I>,
+) -> ConfirmationStatus
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()>,
+    I: FnMut() -> IFut,
+    IFut: Future<Output = Result<()>>,
+{
+    let logger = Logger::new("[CONFIRMATION]".to_string());
+    let start_time = Instant::now();
+    let rebroadcast_interval = import_optional_env_var("REBROADCAST_INTERVAL_MS")
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_REBROADCAST_INTERVAL);
+
+    let pubsub = match ws_url {
+        Some(url) => match PubsubClient::new(url).await {
+            Ok(pubsub) => Some(pubsub),
+            Err(e) => {
+                logger.warning(format!("signatureSubscribe unavailable, polling only: {}", e));
+                None
+            }
+        },
+        None => None,
+    };
+
+    let mut subscriptions: Vec<BoxStream<'_, Signature>> = Vec::new();
+    let mut unsubscribes = Vec::new();
+    if let Some(pubsub) = &pubsub {
+        let config = RpcSignatureSubscribeConfig {
+            commitment: Some(CommitmentConfig::confirmed()),
+            enable_received_notification: Some(false),
+        };
+        for signature in signatures {
+            match pubsub.signature_subscribe(signature, Some(config.clone())).await {
+                Ok((stream, unsubscribe)) => {
+                    let signature = *signature;
+                    subscriptions.push(
+                        stream
+                            .filter(|response| {
+                                futures::future::ready(matches!(
+                                    response.value,
+                                    RpcSignatureResult::ProcessedSignature(_)
+                                ))
+                            })
+                            .map(move |_| signature)
+                            .boxed(),
+                    );
+                    unsubscribes.push(unsubscribe);
+                }
+                Err(e) => logger.warning(format!("Failed to subscribe to {}: {}", signature, e)),
+            }
+        }
+    }
+
+    let subscribed = !subscriptions.is_empty();
+    let mut notifications = select_all(subscriptions);
+    let poll_interval = if subscribed { SUBSCRIBED_POLL_INTERVAL } else { rebroadcast_interval };
+    let mut rebroadcast_tick = time::interval(Duration::from_millis(rebroadcast_interval));
+    let mut poll_tick = time::interval(Duration::from_millis(poll_interval));
+    // Both intervals fire immediately; the transactions were only just sent
+    rebroadcast_tick.tick().await;
+
+    // RPC errors are logged and retried on the next tick; only an expired
+    // blockhash (or consumed nonce) ends tracking without a status
+    let status = loop {
+        tokio::select! {
+            Some(signature) = notifications.next(), if subscribed => {
+                // The notification carries no slot, so read it from the status
+                match poll_statuses(client, &[signature], target_slot).await {
+                    Ok(Some(status)) => break status,
+                    Ok(None) => {}
+                    Err(e) => logger.warning(format!("Failed to read status of {}: {}", signature, e)),
+                }
+            }
+            _ = rebroadcast_tick.tick() => rebroadcast().await,
+            _ = poll_tick.tick() => {
+                match poll_statuses(client, signatures, target_slot).await {
+                    Ok(Some(status)) => break status,
+                    Ok(None) => {}
+                    Err(e) => logger.warning(format!("Failed to poll signature statuses: {}", e)),
+                }
+                match client.get_block_height().await {
+                    Ok(block_height) if block_height > last_valid_block_height => {
+                        if let Some(invalidate) = invalidate.as_mut() {
+                            if let Err(e) = invalidate().await {
+                                logger.warning(format!("Failed to invalidate the durable nonce: {}", e));
+                                continue;
+                            }
+                        }
+                        // It may still have landed right before the blockhash
+                        // expired or the nonce was consumed
+                        break poll_statuses(client, signatures, target_slot)
+                            .await
+                            .unwrap_or_else(|e| {
+                                logger.warning(format!("Failed to poll signature statuses: {}", e));
+                                None
+                            })
+                            .unwrap_or(ConfirmationStatus::Expired);
+                    }
+                    Ok(_) => {}
+                    Err(e) => logger.warning(format!("Failed to get block height: {}", e)),
+                }
+            }
+        }
+    };
+
+    for unsubscribe in unsubscribes {
+        unsubscribe().await;
+    }
+
+    match &status {
+        ConfirmationStatus::Confirmed { signature, slot, slot_delta } => logger.success(format!(
+            "[CONFIRMED] => Tx: {}, Slot: {}, Slot delta: {}, Time: {:?}",
+            signature,
+            slot,
+            slot_delta.map(|delta| delta.to_string()).unwrap_or_else(|| "?".to_string()),
+            start_time.elapsed()
+        )),
+        ConfirmationStatus::Failed { signature, slot, error } => logger.error(format!(
+            "[FAILED] => Tx: {}, Slot: {}, Error: {}",
+            signature, slot, error
+        )),
+        ConfirmationStatus::Expired => logger.error(format!(
+            "[EXPIRED] => Blockhash expired after {:?} without confirmation",
+            start_time.elapsed()
+        )),
+    }
+
+    status
+}
+
+async fn poll_statuses(
+    client: &RpcClient,
+    signatures: &[Signature],
+    target_slot: Option<u64>,
+) -> Result<Option<ConfirmationStatus>> {
+    let statuses = client.get_signature_statuses(signatures).await?.value;
+
+    for (signature, status) in signatures.iter().zip(statuses) {
+        let status = match status {
+            Some(status) if status.satisfies_commitment(CommitmentConfig::confirmed()) => status,
+            _ => continue,
+        };
+
+        return Ok(Some(match status.err {
+            Some(error) => ConfirmationStatus::Failed {
+                signature: *signature,
+                slot: status.slot,
+                error,
+            },
+            None => ConfirmationStatus::Confirmed {
+                signature: *signature,
+                slot: status.slot,
+                slot_delta: target_slot.map(|target| status.slot as i64 - target as i64),
+            },
+        }));
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,133 @@
+use {
+    anyhow::{anyhow, Result},
+    std::{
+        collections::HashMap,
+        future::Future,
+        sync::{Arc, Mutex},
+    },
+    tokio::task::JoinHandle,
+};
+
+// Runs swaps for different mints in parallel while keeping the ones for the
+// same mint in dispatch order: each task waits for the previous task on its
+// mint before starting, so a buy is never overtaken by the sell after it.
+#[derive(Clone, Default)]
+pub struct MintDispatcher {
+    // Last task dispatched per mint
+    tails: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
+}
+
+impl MintDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn dispatch<F>(&self, mint: &str, task: F) -> Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mut tails = self.tails.lock().map_err(|e| anyhow!("{}", e))?;
+        tails.retain(|_, tail| !tail.is_finished());
+
+        let previous = tails.remove(mint);
+        let tail = tokio::spawn(async move {
+            if let Some(previous) = previous {
+                // A panicked predecessor still counts as done
+                let _ = previous.await;
+            }
+            task.await;
+        });
+        tails.insert(mint.to_string(), tail);
+        Ok(())
+    }
+
+    // Mints with a task queued or running.
+    pub fn in_flight(&self) -> usize {
+        self.tails
+            .lock()
+            .map(|tails| tails.values().filter(|tail| !tail.is_finished()).count())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::time::Duration,
+        tokio::{
+            sync::{mpsc, oneshot},
+            time::{sleep, timeout},
+        },
+    };
+
+    async fn collect(done: &mut mpsc::UnboundedReceiver<&'static str>, count: usize) -> Vec<&'static str> {
+        let mut order = Vec::new();
+        while order.len() < count {
+            let next = timeout(Duration::from_secs(5), done.recv()).await.expect("tasks stalled");
+            order.push(next.unwrap());
+        }
+        order
+    }
+
+    #[tokio::test]
+    async fn same_mint_runs_in_dispatch_order() {
+        let dispatcher = MintDispatcher::new();
+        let (done_tx, mut done) = mpsc::unbounded_channel();
+
+        // Earlier tasks take longer, so only the ordering keeps them in line
+        for (name, delay) in [("buy", 60), ("sell", 30), ("exit", 0)] {
+            let done_tx = done_tx.clone();
+            dispatcher
+                .dispatch("mint", async move {
+                    sleep(Duration::from_millis(delay)).await;
+                    done_tx.send(name).unwrap();
+                })
+                .unwrap();
+        }
+        assert_eq!(dispatcher.in_flight(), 1);
+
+        assert_eq!(collect(&mut done, 3).await, vec!["buy", "sell", "exit"]);
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(dispatcher.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn different_mints_run_in_parallel() {
+        let dispatcher = MintDispatcher::new();
+        let (done_tx, mut done) = mpsc::unbounded_channel();
+        let (unblock_tx, unblock) = oneshot::channel();
+
+        // The first mint's task can only finish once the second one has run
+        let first_done = done_tx.clone();
+        dispatcher
+            .dispatch("first", async move {
+                unblock.await.unwrap();
+                first_done.send("first").unwrap();
+            })
+            .unwrap();
+        dispatcher
+            .dispatch("second", async move {
+                done_tx.send("second").unwrap();
+                unblock_tx.send(()).unwrap();
+            })
+            .unwrap();
+
+        assert_eq!(collect(&mut done, 2).await, vec!["second", "first"]);
+    }
+
+    #[tokio::test]
+    async fn a_panicked_task_does_not_block_its_mint() {
+        let dispatcher = MintDispatcher::new();
+        let (done_tx, mut done) = mpsc::unbounded_channel();
+
+        dispatcher.dispatch("mint", async { panic!("swap task panicked") }).unwrap();
+        dispatcher
+            .dispatch("mint", async move {
+                done_tx.send("next").unwrap();
+            })
+            .unwrap();
+
+        assert_eq!(collect(&mut done, 1).await, vec!["next"]);
+    }
+}
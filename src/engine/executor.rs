@@ -0,0 +1,533 @@
+use {
+    crate::{
+        common::{logger::Logger, utils::{import_optional_env_var, SwapConfig, SwapDirection}},
+        dex::{
+            pump_error::PumpError,
+            pump_fun::{Pump, PumpInfo, SimulationFailure},
+        },
+        engine::{
+            confirmation::{track_confirmation, ConfirmationStatus},
+            journal::{Decision, Journal, JournalRecord},
+            paper::PaperPortfolio,
+            positions::Fill,
+        },
+        services::{jito::{self, BundleOutcome, TipConfig}, relayer},
+    },
+    anyhow::{anyhow, Result},
+    futures::future::join_all,
+    solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig},
+    solana_sdk::{signature::Signature, transaction::VersionedTransaction},
+    std::{
+        collections::BTreeMap,
+        fmt,
+        future::Future,
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tokio::sync::oneshot,
+};
+
+const BUNDLE_OUTCOME_GRACE: u64 = 2; // seconds
+
+// Per-path send and landing counters for the lifetime of the process
+static PATH_STATS: Mutex<BTreeMap<String, PathStats>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SendPath {
+    Rpc,
+    Jito,
+    Relayer(String),
+    // Simulated fill in paper trading mode
+    Paper,
+}
+
+impl fmt::Display for SendPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendPath::Rpc => write!(f, "rpc"),
+            SendPath::Jito => write!(f, "jito"),
+            SendPath::Relayer(url) => {
+                let host = reqwest::Url::parse(url)
+                    .ok()
+                    .and_then(|url| url.host_str().map(|host| host.to_string()))
+                    .unwrap_or_else(|| url.clone());
+                write!(f, "relayer:{}", host)
+            }
+            SendPath::Paper => write!(f, "paper"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PathStats {
+    pub sent: u64,
+    pub accepted: u64,
+    pub landed: u64,
+    pub total_ack_latency: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct PathResult {
+    pub path: SendPath,
+    pub signature: String,
+    pub ack_latency: Duration,
+    pub error: Option<String>,
+    pub bundle_id: Option<String>,
+    pub bundle_outcome: Option<BundleOutcome>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SwapResult {
+    pub signature: String,
+    pub landed_path: Option<SendPath>,
+    pub landed_slot: Option<u64>,
+    pub slot_delta: Option<i64>,
+    pub paths: Vec<PathResult>,
+    // Set for paper fills, which have no transaction to read it from
+    pub fill: Option<Fill>,
+}
+
+// The swap landed but the transaction failed on-chain.
+#[derive(Debug, Clone)]
+pub struct OnChainFailure {
+    pub signature: String,
+    pub slot: u64,
+    pub error: String,
+    pub pump_error: Option<PumpError>,
+}
+
+impl fmt::Display for OnChainFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Transaction {} failed on-chain at slot {}: {}",
+            self.signature,
+            self.slot,
+            self.pump_error.map(|e| e.to_string()).unwrap_or_else(|| self.error.clone())
+        )
+    }
+}
+
+impl std::error::Error for OnChainFailure {}
+
+// The Pump program error behind a failed swap, whether it was caught in
+// simulation, on-chain, or before building the transaction.
+pub fn pump_error(error: &anyhow::Error) -> Option<PumpError> {
+    if let Some(failure) = error.downcast_ref::<SimulationFailure>() {
+        return failure.pump_error;
+    }
+    if let Some(failure) = error.downcast_ref::<OnChainFailure>() {
+        return failure.pump_error;
+    }
+    error.downcast_ref::<PumpError>().copied()
+}
+
+impl SwapResult {
+    pub fn landed(&self) -> bool {
+        self.landed_slot.is_some()
+    }
+
+    pub fn bundle_outcome(&self) -> Option<&BundleOutcome> {
+        self.paths.iter().find_map(|path| path.bundle_outcome.as_ref())
+    }
+}
+
+// Sends copy trades through every configured path at once: the RPC node,
+// Jito (when enabled) and each relayer in the comma-separated `RELAYER_URL`.
+pub struct Executor {
+    client: Arc<RpcClient>,
+    ws_url: Option<String>,
+    relayers: Vec<String>,
+    // Where bundle outcomes that arrive after the swap was journaled go
+    journal: Option<Journal>,
+    tip_config: TipConfig,
+    // Fills swaps in place of sending them while paper trading
+    paper: Option<PaperPortfolio>,
+}
+
+impl Executor {
+    pub fn new(client: Arc<RpcClient>, tip_config: TipConfig) -> Self {
+        let relayers = import_optional_env_var("RELAYER_URL")
+            .map(|urls| {
+                urls.split(',')
+                    .map(|url| url.trim().to_string())
+                    .filter(|url| !url.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            client,
+            ws_url: import_optional_env_var("RPC_WSS"),
+            relayers,
+            journal: None,
+            tip_config,
+            paper: None,
+        }
+    }
+
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    pub fn with_paper(mut self, paper: Option<PaperPortfolio>) -> Self {
+        self.paper = paper;
+        self
+    }
+
+    // `target_slot` is the slot of the target's transaction being copied.
+    pub async fn swap(
+        &self,
+        pump: &Pump,
+        pump_info: &PumpInfo,
+        config: SwapConfig,
+        target_slot: Option<u64>,
+    ) -> Result<SwapResult> {
+        let logger = Logger::new("[EXECUTOR]".to_string());
+        if let Some(paper) = &self.paper {
+            return paper.swap(pump, pump_info, &config, target_slot, &self.tip_config).await;
+        }
+
+        let tip = if config.use_jito {
+            let tip_account = jito::get_tip_account()?;
+            let tip_lamports = (jito::get_tip_value(&self.tip_config)? * 1_000_000_000.0) as u64;
+            Some((tip_account, tip_lamports))
+        } else {
+            None
+        };
+
+        // Without a tip every path carries the same signed transaction, so at
+        // most one lands and the durable nonce is not needed. With one, the
+        // shared nonce guarantees that instead, so paths outside Jito can skip
+        // paying the tip. Swaps on other mints and exits sign against the same
+        // nonce, so the lease is held until this swap has resolved.
+        let mut lease = match tip {
+            Some(_) => pump.lease_nonce().await,
+            None => None,
+        };
+        let nonce = match lease.as_mut() {
+            Some(lease) => Some(lease.current(&pump.client).await?),
+            None => None,
+        };
+
+        let (transaction, last_valid_block_height) =
+            pump.build_transaction(pump_info, &config, tip, nonce).await?;
+        let units_consumed = pump.simulate(&transaction, config.use_jito).await?;
+        logger.debug(format!("[SIMULATED] => {} CU", units_consumed.unwrap_or_default()));
+
+        let untipped = if nonce.is_some() {
+            pump.build_transaction(pump_info, &config, None, nonce).await?.0
+        } else {
+            transaction.clone()
+        };
+
+        let mut variants = vec![(SendPath::Rpc, untipped.clone())];
+        if config.use_jito {
+            variants.push((SendPath::Jito, transaction.clone()));
+        }
+        for relayer_url in &self.relayers {
+            variants.push((SendPath::Relayer(relayer_url.clone()), untipped.clone()));
+        }
+
+        let invalidate = lease
+            .as_ref()
+            .zip(nonce)
+            .map(|(lease, nonce)| move || lease.invalidate(&pump.client, &pump.keypair, nonce));
+        let follow_up = JournalRecord::new(
+            "bundle",
+            &pump_info.mint,
+            matches!(config.swap_direction, SwapDirection::Buy),
+        );
+        let result = self
+            .broadcast(variants, last_valid_block_height, target_slot, invalidate, follow_up)
+            .await;
+
+        if let Some((lease, nonce)) = lease.zip(nonce) {
+            lease.refresh(pump.client.clone(), nonce);
+        }
+        result
+    }
+
+    // `invalidate` consumes the durable nonce the variants were signed
+    // against, when they were. `follow_up` is the journal record for a bundle
+    // outcome that arrives too late to be part of the result.
+    pub async fn broadcast<I, IFut>(
+        &self,
+        variants: Vec<(SendPath, VersionedTransaction)>,
+        last_valid_block_height: u64,
+        target_slot: Option<u64>,
+        invalidate: Option<I>,
+        follow_up: JournalRecord,
+    ) -> Result<SwapResult>
+    where
+        I: FnMut() -> IFut,
+        IFut: Future<Output = Result<()>>,
+    {
+        let logger = Logger::new("[EXECUTOR]".to_string());
+        let start_time = Instant::now();
+
+        // Bundles are not resent; a rebroadcast bundle would pay the tip again
+        let rebroadcastable: Vec<(SendPath, VersionedTransaction)> = variants
+            .iter()
+            .filter(|(path, _)| *path != SendPath::Jito)
+            .cloned()
+            .collect();
+
+        let mut paths = join_all(
+            variants
+                .into_iter()
+                .map(|(path, transaction)| self.send_path(path, transaction, start_time)),
+        )
+        .await;
+
+        for path in &paths {
+            match &path.error {
+                None => logger.info(format!(
+                    "[SENT] => Path: {}, Tx: {}, Ack: {:?}{}",
+                    path.path,
+                    path.signature,
+                    path.ack_latency,
+                    path.bundle_id
+                        .as_ref()
+                        .map(|id| format!(", Bundle: {}", id))
+                        .unwrap_or_default()
+                )),
+                Some(e) => logger.error(format!("[SEND FAILED] => Path: {}, Error: {}", path.path, e)),
+            }
+        }
+
+        let mut accepted: Vec<Signature> = Vec::new();
+        for path in paths.iter().filter(|path| path.error.is_none()) {
+            let signature = Signature::from_str(&path.signature)?;
+            if !accepted.contains(&signature) {
+                accepted.push(signature);
+            }
+        }
+        if accepted.is_empty() {
+            record_path_stats(&paths, None);
+            return Err(anyhow!("All {} send paths failed", paths.len()));
+        }
+
+        let bundles: Vec<(usize, String)> = paths
+            .iter()
+            .enumerate()
+            .filter_map(|(index, path)| path.bundle_id.clone().map(|id| (index, id)))
+            .collect();
+        // Bundle outcomes are tracked in the background so a bundle that lost
+        // the race does not hold the swap until its status times out. One
+        // that arrives after the swap was journaled gets a record of its own.
+        let bundle_outcomes: Vec<_> = bundles
+            .iter()
+            .map(|(index, id)| {
+                let id = id.clone();
+                let (sender, receiver) = oneshot::channel();
+                let follow_up = self.journal.clone().map(|journal| {
+                    let record = JournalRecord {
+                        signature: Some(paths[*index].signature.clone()),
+                        send_path: Some(SendPath::Jito.to_string()),
+                        detail: Some(format!("Bundle {}", id)),
+                        ..follow_up.clone()
+                    };
+                    (journal, record)
+                });
+                tokio::spawn(async move {
+                    let outcome = jito::wait_for_bundle(&id).await;
+                    let logger = Logger::new("[EXECUTOR]".to_string());
+                    match &outcome {
+                        Ok(outcome) => logger.info(format!("[BUNDLE] => Id: {}, Outcome: {:?}", id, outcome)),
+                        Err(e) => logger.warning(format!("Failed to track bundle {}: {}", id, e)),
+                    }
+                    if let (Err(Ok(outcome)), Some((journal, mut record))) = (sender.send(outcome), follow_up) {
+                        record.decision = match &outcome {
+                            BundleOutcome::Landed { slot } => {
+                                record.landed_slot = *slot;
+                                Decision::Copied
+                            }
+                            _ => Decision::NotLanded,
+                        };
+                        record.bundle_outcome = Some(format!("{:?}", outcome));
+                        journal.append(&record);
+                    }
+                });
+                (*index, receiver)
+            })
+            .collect();
+
+        let confirmation = track_confirmation(
+            &self.client,
+            self.ws_url.as_deref(),
+            &accepted,
+            last_valid_block_height,
+            target_slot,
+            || self.rebroadcast(&rebroadcastable),
+            invalidate,
+        )
+        .await;
+
+        // A landed bundle reports shortly after confirmation; the rest are
+        // left to finish, and be journaled, on their own
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(BUNDLE_OUTCOME_GRACE);
+        for (index, mut receiver) in bundle_outcomes {
+            let outcome = match tokio::time::timeout_at(deadline, &mut receiver).await {
+                Ok(outcome) => outcome.ok(),
+                Err(_) => {
+                    // Closing first means an outcome is either taken here or
+                    // handed back to its task, never lost in between
+                    receiver.close();
+                    receiver.try_recv().ok()
+                }
+            };
+            if let Some(Ok(outcome)) = outcome {
+                paths[index].bundle_outcome = Some(outcome);
+            }
+        }
+
+        let landed = match &confirmation {
+            ConfirmationStatus::Confirmed { signature, slot, slot_delta } => {
+                Some((signature.to_string(), *slot, *slot_delta))
+            }
+            _ => None,
+        };
+        let landed_path = landed
+            .as_ref()
+            .and_then(|(signature, _, _)| attribute_landing(&paths, signature));
+        record_path_stats(&paths, landed_path.as_ref());
+        log_path_stats();
+
+        if let ConfirmationStatus::Failed { signature, slot, error } = confirmation {
+            return Err(OnChainFailure {
+                signature: signature.to_string(),
+                slot,
+                error: error.to_string(),
+                pump_error: PumpError::from_transaction_error(&error),
+            }
+            .into());
+        }
+
+        Ok(match landed {
+            Some((signature, slot, slot_delta)) => SwapResult {
+                signature,
+                landed_path,
+                landed_slot: Some(slot),
+                slot_delta,
+                paths,
+                fill: None,
+            },
+            None => SwapResult {
+                signature: accepted[0].to_string(),
+                landed_path: None,
+                landed_slot: None,
+                slot_delta: None,
+                paths,
+                fill: None,
+            },
+        })
+    }
+
+    async fn rebroadcast(&self, variants: &[(SendPath, VersionedTransaction)]) {
+        let logger = &Logger::new("[EXECUTOR]".to_string());
+        let resends = variants.iter().map(|(path, transaction)| async move {
+            let resent = match path {
+                SendPath::Rpc => self.send_rpc(transaction).await,
+                SendPath::Relayer(url) => relayer::send_transaction(url, transaction).await.map(|_| ()),
+                SendPath::Jito | SendPath::Paper => Ok(()),
+            };
+            if let Err(e) = resent {
+                logger.debug(format!("Rebroadcast via {} failed: {}", path, e));
+            }
+        });
+        join_all(resends).await;
+    }
+
+    async fn send_rpc(&self, transaction: &VersionedTransaction) -> Result<()> {
+        let config = RpcSendTransactionConfig {
+            skip_preflight: true,
+            max_retries: Some(0),
+            ..RpcSendTransactionConfig::default()
+        };
+        self.client
+            .send_transaction_with_config(transaction, config)
+            .await
+            .map(|_| ())
+            .map_err(|e| anyhow!(e))
+    }
+
+    async fn send_path(&self, path: SendPath, transaction: VersionedTransaction, start_time: Instant) -> PathResult {
+        let signature = transaction.signatures[0].to_string();
+        let mut bundle_id = None;
+
+        let sent = match &path {
+            SendPath::Rpc => self.send_rpc(&transaction).await,
+            SendPath::Jito => jito::send_bundle(std::slice::from_ref(&transaction))
+                .await
+                .map(|id| bundle_id = Some(id)),
+            SendPath::Relayer(url) => relayer::send_transaction(url, &transaction).await.map(|_| ()),
+            SendPath::Paper => Err(anyhow!("Paper fills are never sent")),
+        };
+
+        PathResult {
+            path,
+            signature,
+            ack_latency: start_time.elapsed(),
+            error: sent.err().map(|e| e.to_string()),
+            bundle_id,
+            bundle_outcome: None,
+        }
+    }
+}
+
+// A landed Jito bundle is certain; otherwise credit the quickest non-Jito
+// path that accepted the landed signature.
+fn attribute_landing(paths: &[PathResult], signature: &str) -> Option<SendPath> {
+    let carried: Vec<&PathResult> = paths
+        .iter()
+        .filter(|path| path.error.is_none() && path.signature == signature)
+        .collect();
+
+    carried
+        .iter()
+        .find(|path| matches!(path.bundle_outcome, Some(BundleOutcome::Landed { .. })))
+        .or_else(|| {
+            carried
+                .iter()
+                .filter(|path| path.path != SendPath::Jito)
+                .min_by_key(|path| path.ack_latency)
+        })
+        .map(|path| path.path.clone())
+}
+
+fn record_path_stats(paths: &[PathResult], landed_path: Option<&SendPath>) {
+    if let Ok(mut stats) = PATH_STATS.lock() {
+        for path in paths {
+            let entry = stats.entry(path.path.to_string()).or_default();
+            entry.sent += 1;
+            if path.error.is_none() {
+                entry.accepted += 1;
+                entry.total_ack_latency += path.ack_latency;
+            }
+            if landed_path == Some(&path.path) {
+                entry.landed += 1;
+            }
+        }
+    }
+}
+
+pub fn path_stats() -> BTreeMap<String, PathStats> {
+    PATH_STATS.lock().map(|stats| stats.clone()).unwrap_or_default()
+}
+
+fn log_path_stats() {
+    let logger = Logger::new("[EXECUTOR]".to_string());
+    for (path, stats) in path_stats() {
+        logger.info(format!(
+            "[PATH STATS] => {}: landed {}/{} sent, accepted {}, avg ack {:?}",
+            path,
+            stats.landed,
+            stats.sent,
+            stats.accepted,
+            stats.total_ack_latency / stats.accepted.max(1) as u32
+        ));
+    }
+}
@@ -0,0 +1,644 @@
+use {
+    crate::{
+        common::{
+            logger::Logger,
+            utils::{import_optional_env_var, AppState, SwapConfig, SwapDirection},
+        },
+        dex::pump_fun::{BondingCurveAccount, Pump, PumpInfo},
+        engine::{
+            dispatcher::MintDispatcher,
+            executor::Executor,
+            journal::{Journal, JournalRecord},
+            paper::{self, PaperPortfolio},
+            positions::PositionBook,
+        },
+    },
+    anyhow::{anyhow, Result},
+    solana_sdk::{pubkey::Pubkey, signature::Signer},
+    std::{
+        collections::HashMap,
+        fmt,
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tokio::time,
+};
+
+const DEFAULT_EXIT_POLL_INTERVAL: u64 = 1000; // milliseconds
+
+// When to sell a position, as multiples of its entry price.
+#[derive(Debug, Clone, Default)]
+pub struct ExitRules {
+    // (price multiple, percent of the remaining holding to sell), ascending
+    pub take_profit: Vec<(f64, u64)>,
+    pub stop_loss: Option<f64>,
+    // Percent below the peak price, armed once the position is in profit
+    pub trailing_stop: Option<u64>,
+    // Sell after holding this long, whatever the target does
+    pub max_hold: Option<Duration>,
+    // Sell when the target hasn't sold the mint for this long since our entry
+    pub target_idle: Option<Duration>,
+    // Percent of the holding a time exit sells; it fires again every period
+    // until the position is closed
+    pub time_sell_percent: u64,
+}
+
+impl ExitRules {
+    // `EXIT_TAKE_PROFIT` is a comma-separated list of `multiple:percent` levels
+    // (a bare multiple sells everything), e.g. "2:50,4:100". `EXIT_STOP_LOSS` is
+    // a multiple such as 0.6 and `EXIT_TRAILING_STOP` a percent. Time exits use
+    // `EXIT_MAX_HOLD_SECS`, `EXIT_TARGET_IDLE_SECS` and `EXIT_TIME_SELL_PERCENT`.
+    pub fn from_env() -> Result<Self> {
+        let mut take_profit = Vec::new();
+        if let Some(levels) = import_optional_env_var("EXIT_TAKE_PROFIT") {
+            for level in levels.split(',').map(|level| level.trim()).filter(|level| !level.is_empty()) {
+                let (multiple, percent) = level.split_once(':').unwrap_or((level, "100"));
+                let multiple = multiple
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|e| anyhow!("Invalid EXIT_TAKE_PROFIT level {}: {}", level, e))?;
+                let percent = percent
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|e| anyhow!("Invalid EXIT_TAKE_PROFIT level {}: {}", level, e))?
+                    .min(100);
+                take_profit.push((multiple, percent));
+            }
+            take_profit.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+
+        let stop_loss = import_optional_env_var("EXIT_STOP_LOSS")
+            .map(|v| v.parse::<f64>().map_err(|e| anyhow!("Invalid EXIT_STOP_LOSS: {}", e)))
+            .transpose()?;
+        let trailing_stop = import_optional_env_var("EXIT_TRAILING_STOP")
+            .map(|v| v.parse::<u64>().map_err(|e| anyhow!("Invalid EXIT_TRAILING_STOP: {}", e)))
+            .transpose()?;
+        let max_hold = import_optional_env_var("EXIT_MAX_HOLD_SECS")
+            .map(|v| v.parse::<u64>().map_err(|e| anyhow!("Invalid EXIT_MAX_HOLD_SECS: {}", e)))
+            .transpose()?
+            .map(Duration::from_secs);
+        let target_idle = import_optional_env_var("EXIT_TARGET_IDLE_SECS")
+            .map(|v| v.parse::<u64>().map_err(|e| anyhow!("Invalid EXIT_TARGET_IDLE_SECS: {}", e)))
+            .transpose()?
+            .map(Duration::from_secs);
+        let time_sell_percent = import_optional_env_var("EXIT_TIME_SELL_PERCENT")
+            .map(|v| v.parse::<u64>().map_err(|e| anyhow!("Invalid EXIT_TIME_SELL_PERCENT: {}", e)))
+            .transpose()?
+            .unwrap_or(100)
+            .min(100);
+
+        Ok(Self {
+            take_profit,
+            stop_loss,
+            trailing_stop,
+            max_hold,
+            target_idle,
+            time_sell_percent,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.take_profit.is_empty()
+            && self.stop_loss.is_none()
+            && self.trailing_stop.is_none()
+            && self.max_hold.is_none()
+            && self.target_idle.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExitReason {
+    TakeProfit(f64),
+    StopLoss,
+    TrailingStop,
+    MaxHold,
+    TargetIdle,
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitReason::TakeProfit(multiple) => write!(f, "take profit at {}x", multiple),
+            ExitReason::StopLoss => write!(f, "stop loss"),
+            ExitReason::TrailingStop => write!(f, "trailing stop"),
+            ExitReason::MaxHold => write!(f, "max hold time"),
+            ExitReason::TargetIdle => write!(f, "target idle"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExitPosition {
+    pub pump_info: PumpInfo,
+    pub rules: ExitRules,
+    // Curve spot price right after our buys landed, weighted by the tokens
+    // each bought, or else the first curve price seen. Fees are left out so
+    // it compares like for like with the spot price exits are measured at.
+    pub entry_price: Option<f64>,
+    pub entry_tokens: u64,
+    pub peak_price: f64,
+    // Index of the next take-profit level to fire
+    pub next_level: usize,
+    pub selling: bool,
+    pub opened_at: Instant,
+    pub last_target_sell: Option<Instant>,
+    // Time exits measure from here; moved forward after each one lands
+    pub time_exit_from: Instant,
+}
+
+impl ExitPosition {
+    pub fn new(pump_info: &PumpInfo, rules: &ExitRules, now: Instant) -> Self {
+        Self {
+            pump_info: pump_info.clone(),
+            rules: rules.clone(),
+            entry_price: None,
+            entry_tokens: 0,
+            peak_price: 0.0,
+            next_level: 0,
+            selling: false,
+            opened_at: now,
+            last_target_sell: None,
+            time_exit_from: now,
+        }
+    }
+
+    pub fn evaluate(&mut self, price: f64) -> Option<(ExitReason, u64)> {
+        self.evaluate_at(price, Instant::now())
+    }
+
+    // Updates the peak and returns the exit to take at `price`, if any, along
+    // with the percent of the holding to sell. Time exits are measured up to
+    // `now`, which a backtest sets from the replayed clock.
+    pub fn evaluate_at(&mut self, price: f64, now: Instant) -> Option<(ExitReason, u64)> {
+        let entry_price = *self.entry_price.get_or_insert(price);
+        self.peak_price = self.peak_price.max(price);
+
+        if let Some(stop_loss) = self.rules.stop_loss {
+            if price <= entry_price * stop_loss {
+                return Some((ExitReason::StopLoss, 100));
+            }
+        }
+        if let Some(trailing_stop) = self.rules.trailing_stop {
+            if self.peak_price > entry_price
+                && price <= self.peak_price * (100 - trailing_stop.min(100)) as f64 / 100.0
+            {
+                return Some((ExitReason::TrailingStop, 100));
+            }
+        }
+        if let Some((multiple, percent)) = self.rules.take_profit.get(self.next_level) {
+            if price >= entry_price * multiple {
+                return Some((ExitReason::TakeProfit(*multiple), *percent));
+            }
+        }
+
+        let held = now.saturating_duration_since(self.time_exit_from);
+        if self.rules.max_hold.is_some_and(|max_hold| held >= max_hold) {
+            return Some((ExitReason::MaxHold, self.rules.time_sell_percent));
+        }
+        let target_sold = self
+            .last_target_sell
+            .is_some_and(|last_target_sell| last_target_sell >= self.time_exit_from);
+        if !target_sold && self.rules.target_idle.is_some_and(|target_idle| held >= target_idle) {
+            return Some((ExitReason::TargetIdle, self.rules.time_sell_percent));
+        }
+        None
+    }
+
+    // Folds a landed buy of `tokens` at curve price `price` into the entry.
+    pub fn add_entry(&mut self, price: f64, tokens: u64) {
+        let held = self.entry_price.map(|_| self.entry_tokens).unwrap_or_default();
+        let total = held + tokens;
+        if total == 0 {
+            return;
+        }
+        let weighted = self.entry_price.unwrap_or_default() * held as f64 + price * tokens as f64;
+        self.entry_price = Some(weighted / total as f64);
+        self.entry_tokens = total;
+    }
+
+    // Re-arms the position after a sell attempt; a landed take-profit moves on
+    // to the next level and a landed time exit restarts its clock.
+    pub fn finish_sell(&mut self, landed: bool, reason: ExitReason, now: Instant) {
+        self.selling = false;
+        match reason {
+            ExitReason::TakeProfit(_) if landed => self.next_level += 1,
+            ExitReason::MaxHold | ExitReason::TargetIdle if landed => self.time_exit_from = now,
+            _ => {}
+        }
+    }
+}
+
+// Watches the bonding curve of every held mint and sells on take-profit,
+// stop-loss, trailing-stop and time triggers, independently of the target's
+// sells.
+#[derive(Clone)]
+pub struct ExitManager {
+    rules: ExitRules,
+    positions: Arc<Mutex<HashMap<String, ExitPosition>>>,
+    book: PositionBook,
+    journal: Journal,
+    dispatcher: MintDispatcher,
+    paper: Option<PaperPortfolio>,
+}
+
+impl ExitManager {
+    pub fn new(
+        rules: ExitRules,
+        book: PositionBook,
+        journal: Journal,
+        dispatcher: MintDispatcher,
+        paper: Option<PaperPortfolio>,
+    ) -> Self {
+        Self {
+            rules,
+            positions: Arc::new(Mutex::new(HashMap::new())),
+            book,
+            journal,
+            dispatcher,
+            paper,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    // Starts watching a mint after a landed buy. A mint already watched keeps
+    // its progress through the take-profit levels, and folds the buy's curve
+    // price and tokens into its entry when given.
+    pub fn register(&self, pump_info: &PumpInfo, entry: Option<(f64, u64)>) {
+        if !self.is_enabled() {
+            return;
+        }
+        if let Ok(mut positions) = self.positions.lock() {
+            let position = positions
+                .entry(pump_info.mint.clone())
+                .or_insert_with(|| ExitPosition::new(pump_info, &self.rules, Instant::now()));
+            if let Some((price, tokens)) = entry {
+                position.add_entry(price, tokens);
+            }
+        }
+    }
+
+    // The target sold `mint`, which holds off its target-idle exit.
+    pub fn record_target_sell(&self, mint: &str) {
+        if let Ok(mut positions) = self.positions.lock() {
+            if let Some(position) = positions.get_mut(mint) {
+                position.last_target_sell = Some(Instant::now());
+            }
+        }
+    }
+
+    pub fn remove(&self, mint: &str) {
+        if let Ok(mut positions) = self.positions.lock() {
+            positions.remove(mint);
+        }
+    }
+
+    pub fn positions(&self) -> Vec<ExitPosition> {
+        self.positions
+            .lock()
+            .map(|positions| positions.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub async fn run(self, state: AppState, slippage: u64, use_jito: bool) {
+        let logger = Logger::new("[EXIT MANAGER]".to_string());
+        let poll_interval = import_optional_env_var("EXIT_POLL_INTERVAL_MS")
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_EXIT_POLL_INTERVAL);
+        let mut interval = time::interval(Duration::from_millis(poll_interval));
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check_positions(&state, slippage, use_jito).await {
+                logger.error(format!("Failed to check positions: {}", e));
+            }
+        }
+    }
+
+    async fn check_positions(&self, state: &AppState, slippage: u64, use_jito: bool) -> Result<()> {
+        let logger = Logger::new("[EXIT MANAGER]".to_string());
+        let watched: Vec<(String, Pubkey)> = self
+            .positions()
+            .into_iter()
+            .filter(|position| !position.selling)
+            .map(|position| Ok((position.pump_info.mint.clone(), Pubkey::from_str(&position.pump_info.bonding_curve)?)))
+            .collect::<Result<_>>()?;
+        if watched.is_empty() {
+            return Ok(());
+        }
+
+        let bonding_curves: Vec<Pubkey> = watched.iter().map(|(_, bonding_curve)| *bonding_curve).collect();
+        let accounts = state.rpc_nonblocking_client.get_multiple_accounts(&bonding_curves).await?;
+
+        for ((mint, _), account) in watched.iter().zip(accounts) {
+            let Some(account) = account else { continue };
+            let curve = match BondingCurveAccount::decode(&account.data) {
+                Ok(curve) => curve,
+                Err(e) => {
+                    logger.warning(format!("Failed to decode bonding curve of {}: {}", mint, e));
+                    continue;
+                }
+            };
+
+            let trigger = {
+                let mut positions = self.positions.lock().map_err(|e| anyhow!("{}", e))?;
+                let Some(position) = positions.get_mut(mint) else { continue };
+                if curve.complete {
+                    // Migrated off Pump; nothing left to sell on the curve
+                    logger.warning(format!("[UNWATCH] => {} - Bonding curve complete", mint));
+                    positions.remove(mint);
+                    continue;
+                }
+                if curve.virtual_token_reserves == 0 {
+                    continue;
+                }
+
+                position.pump_info = PumpInfo {
+                    virtual_sol_reserves: curve.virtual_sol_reserves,
+                    virtual_token_reserves: curve.virtual_token_reserves,
+                    real_sol_reserves: curve.real_sol_reserves,
+                    real_token_reserves: curve.real_token_reserves,
+                    complete: curve.complete,
+                    ..position.pump_info.clone()
+                };
+                let price = curve.virtual_sol_reserves as f64 / curve.virtual_token_reserves as f64;
+                let trigger = position.evaluate(price);
+                if trigger.is_some() {
+                    position.selling = true;
+                }
+                trigger.map(|(reason, percent)| (reason, percent, position.clone(), price))
+            };
+
+            if let Some((reason, percent, position, price)) = trigger {
+                logger.info(format!(
+                    "[TRIGGER] => {} - {} at {:.2}x entry after {:?}, selling {}%",
+                    mint,
+                    reason,
+                    price / position.entry_price.unwrap_or(price),
+                    position.opened_at.elapsed(),
+                    percent
+                ));
+                // Queued behind any copy still working on this mint
+                let manager = self.clone();
+                let state = state.clone();
+                self.dispatcher.dispatch(mint, async move {
+                    manager.sell(&state, position, reason, percent, slippage, use_jito).await;
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn sell(
+        &self,
+        state: &AppState,
+        position: ExitPosition,
+        reason: ExitReason,
+        percent: u64,
+        slippage: u64,
+        use_jito: bool,
+    ) {
+        let logger = Logger::new("[EXIT MANAGER]".to_string());
+        let mint = position.pump_info.mint.clone();
+        let pump = Pump::new(
+            state.rpc_nonblocking_client.clone(),
+            state.wallet.clone(),
+            state.blockhash_cache.clone(),
+            state.nonce.clone(),
+            state.lookup_table.clone(),
+        );
+
+        let balance = match paper::get_token_balance(self.paper.as_ref(), &pump, &mint).await {
+            Ok(balance) => balance,
+            Err(e) => {
+                logger.error(format!("Failed to get token balance of {}: {}", mint, e));
+                self.finish_sell(&mint, false, reason);
+                return;
+            }
+        };
+        if balance == 0 {
+            // Sold elsewhere, e.g. by mirroring the target
+            logger.info(format!("[UNWATCH] => {} - No tokens left", mint));
+            self.remove(&mint);
+            return;
+        }
+
+        let amount = (balance as u128 * percent as u128 / 100) as u64;
+        let swap_config = SwapConfig {
+            slippage,
+            use_jito,
+            amount,
+            swap_direction: SwapDirection::Sell,
+        };
+        let start_time = Instant::now();
+        let result = Executor::new(pump.client.clone(), state.tip_config.clone())
+            .with_journal(self.journal.clone())
+            .with_paper(self.paper.clone())
+            .swap(&pump, &position.pump_info, swap_config, None)
+            .await;
+        let mut record = JournalRecord::new("exit", &mint, false);
+        record.exit_reason = Some(reason.to_string());
+        record.latency.execution_ms = Some(start_time.elapsed().as_millis() as u64);
+        record = record.with_swap(&result);
+
+        let landed = match result {
+            Ok(swap_result) if swap_result.landed() => {
+                logger.success(format!(
+                    "[EXIT] => {} - {}: sold {} tokens, Tx: {}",
+                    mint, reason, amount, swap_result.signature
+                ));
+                match self
+                    .book
+                    .record_fill(&pump.client, &state.wallet.pubkey(), &mint, &swap_result)
+                    .await
+                {
+                    Ok((fill, _)) => record.fill = Some(fill),
+                    Err(e) => logger.error(format!("Failed to record fill of {}: {}", swap_result.signature, e)),
+                }
+                true
+            }
+            Ok(swap_result) => {
+                logger.error(format!("[EXIT] => {} - {}: not landed: {}", mint, reason, swap_result.signature));
+                false
+            }
+            Err(e) => {
+                logger.error(format!("[EXIT] => {} - {}: sell failed: {}", mint, reason, e));
+                false
+            }
+        };
+
+        record.latency.total_ms = Some(start_time.elapsed().as_millis() as u64);
+        self.journal.append(&record);
+
+        if landed && amount == balance {
+            self.remove(&mint);
+        } else {
+            self.finish_sell(&mint, landed, reason);
+        }
+    }
+
+    fn finish_sell(&self, mint: &str, landed: bool, reason: ExitReason) {
+        if let Ok(mut positions) = self.positions.lock() {
+            if let Some(position) = positions.get_mut(mint) {
+                position.finish_sell(landed, reason, Instant::now());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(rules: ExitRules, now: Instant) -> ExitPosition {
+        let pump_info = PumpInfo {
+            mint: Pubkey::new_unique().to_string(),
+            bonding_curve: Pubkey::new_unique().to_string(),
+            associated_bonding_curve: Pubkey::new_unique().to_string(),
+            virtual_sol_reserves: 30_000_000_000,
+            virtual_token_reserves: 1_073_000_000_000_000,
+            real_sol_reserves: 0,
+            real_token_reserves: 793_100_000_000_000,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+            creator: None,
+        };
+        let mut position = ExitPosition::new(&pump_info, &rules, now);
+        position.entry_price = Some(1.0);
+        position
+    }
+
+    #[test]
+    fn take_profit_fires_each_level_once_it_lands() {
+        let now = Instant::now();
+        let rules = ExitRules {
+            take_profit: vec![(2.0, 50), (4.0, 100)],
+            ..ExitRules::default()
+        };
+        let mut position = position(rules, now);
+
+        assert_eq!(position.evaluate_at(1.5, now), None);
+        assert_eq!(position.evaluate_at(2.0, now), Some((ExitReason::TakeProfit(2.0), 50)));
+        // A failed sell leaves the level armed
+        position.finish_sell(false, ExitReason::TakeProfit(2.0), now);
+        assert_eq!(position.evaluate_at(2.5, now), Some((ExitReason::TakeProfit(2.0), 50)));
+        position.finish_sell(true, ExitReason::TakeProfit(2.0), now);
+        assert_eq!(position.evaluate_at(3.0, now), None);
+        assert_eq!(position.evaluate_at(4.0, now), Some((ExitReason::TakeProfit(4.0), 100)));
+    }
+
+    #[test]
+    fn stop_loss_measures_from_the_first_price_seen() {
+        let now = Instant::now();
+        let rules = ExitRules {
+            stop_loss: Some(0.6),
+            ..ExitRules::default()
+        };
+        let mut position = position(rules, now);
+        position.entry_price = None;
+
+        assert_eq!(position.evaluate_at(10.0, now), None);
+        assert_eq!(position.entry_price, Some(10.0));
+        assert_eq!(position.evaluate_at(6.5, now), None);
+        assert_eq!(position.evaluate_at(6.0, now), Some((ExitReason::StopLoss, 100)));
+    }
+
+    #[test]
+    fn trailing_stop_arms_once_in_profit() {
+        let now = Instant::now();
+        let rules = ExitRules {
+            trailing_stop: Some(20),
+            ..ExitRules::default()
+        };
+        let mut position = position(rules, now);
+
+        // Not armed while the peak is still the entry
+        assert_eq!(position.evaluate_at(0.7, now), None);
+        assert_eq!(position.evaluate_at(1.5, now), None);
+        assert_eq!(position.peak_price, 1.5);
+        assert_eq!(position.evaluate_at(1.25, now), None);
+        assert_eq!(position.evaluate_at(1.2, now), Some((ExitReason::TrailingStop, 100)));
+    }
+
+    #[test]
+    fn max_hold_fires_every_period_until_closed() {
+        let start = Instant::now();
+        let rules = ExitRules {
+            max_hold: Some(Duration::from_secs(60)),
+            time_sell_percent: 50,
+            ..ExitRules::default()
+        };
+        let mut position = position(rules, start);
+
+        assert_eq!(position.evaluate_at(1.0, start + Duration::from_secs(59)), None);
+        let sold_at = start + Duration::from_secs(60);
+        assert_eq!(position.evaluate_at(1.0, sold_at), Some((ExitReason::MaxHold, 50)));
+        position.finish_sell(true, ExitReason::MaxHold, sold_at);
+        assert_eq!(position.evaluate_at(1.0, sold_at + Duration::from_secs(59)), None);
+        assert_eq!(
+            position.evaluate_at(1.0, sold_at + Duration::from_secs(60)),
+            Some((ExitReason::MaxHold, 50))
+        );
+    }
+
+    #[test]
+    fn target_idle_fires_only_without_a_target_sell_since_the_last_exit() {
+        let start = Instant::now();
+        let rules = ExitRules {
+            target_idle: Some(Duration::from_secs(30)),
+            time_sell_percent: 100,
+            ..ExitRules::default()
+        };
+        let mut position = position(rules, start);
+
+        position.last_target_sell = Some(start + Duration::from_secs(10));
+        assert_eq!(position.evaluate_at(1.0, start + Duration::from_secs(40)), None);
+
+        let sold_at = start + Duration::from_secs(40);
+        position.finish_sell(true, ExitReason::TargetIdle, sold_at);
+        assert_eq!(
+            position.evaluate_at(1.0, sold_at + Duration::from_secs(30)),
+            Some((ExitReason::TargetIdle, 100))
+        );
+    }
+
+    #[test]
+    fn price_exits_come_before_time_exits() {
+        let start = Instant::now();
+        let rules = ExitRules {
+            stop_loss: Some(0.5),
+            max_hold: Some(Duration::from_secs(60)),
+            time_sell_percent: 25,
+            ..ExitRules::default()
+        };
+        let mut position = position(rules, start);
+        let later = start + Duration::from_secs(120);
+
+        assert_eq!(position.evaluate_at(0.4, later), Some((ExitReason::StopLoss, 100)));
+        assert_eq!(position.evaluate_at(0.9, later), Some((ExitReason::MaxHold, 25)));
+    }
+
+    #[test]
+    fn entry_weights_each_buy_by_its_tokens() {
+        let now = Instant::now();
+        let rules = ExitRules {
+            stop_loss: Some(0.8),
+            ..ExitRules::default()
+        };
+        let mut position = position(rules, now);
+        position.entry_price = None;
+
+        position.add_entry(10.0, 1_000);
+        position.add_entry(20.0, 3_000);
+        assert_eq!(position.entry_price, Some(17.5));
+        assert_eq!(position.entry_tokens, 4_000);
+
+        // Measured on the same curve basis, the buy starts above its stop
+        assert_eq!(position.evaluate_at(17.5, now), None);
+        assert_eq!(position.evaluate_at(14.0, now), Some((ExitReason::StopLoss, 100)));
+    }
+}
@@ -0,0 +1,353 @@
+use {
+    crate::{
+        common::{token_metadata::get_token_metadata, utils::import_optional_env_var},
+        dex::pump_fun::{get_curve_progress, get_market_cap, PumpInfo},
+    },
+    anyhow::{anyhow, Result},
+    chrono::Utc,
+    regex::Regex,
+    solana_client::{
+        nonblocking::rpc_client::RpcClient,
+        rpc_client::GetConfirmedSignaturesForAddress2Config,
+    },
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    std::{collections::HashSet, fmt, str::FromStr, time::Duration},
+};
+
+const SIGNATURE_PAGE_LIMIT: usize = 1000;
+const MAX_SIGNATURE_PAGES: usize = 5;
+
+// Why a buy was not copied. `code` is stable for logs and the trade journal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterRejection {
+    MarketCapTooLow { market_cap: f64, min: f64 },
+    MarketCapTooHigh { market_cap: f64, max: f64 },
+    CurveProgressTooHigh { progress: f64, max: f64 },
+    TokenTooNew { age: Duration, min: Duration },
+    MintDenied,
+    MintNotAllowed,
+    CreatorDenied(String),
+    CreatorNotAllowed(Option<String>),
+    NameMismatch(String),
+    SymbolMismatch(String),
+    LookupFailed(String),
+}
+
+impl FilterRejection {
+    pub fn code(&self) -> &'static str {
+        match self {
+            FilterRejection::MarketCapTooLow { .. } => "market_cap_too_low",
+            FilterRejection::MarketCapTooHigh { .. } => "market_cap_too_high",
+            FilterRejection::CurveProgressTooHigh { .. } => "curve_progress_too_high",
+            FilterRejection::TokenTooNew { .. } => "token_too_new",
+            FilterRejection::MintDenied => "mint_denied",
+            FilterRejection::MintNotAllowed => "mint_not_allowed",
+            FilterRejection::CreatorDenied(_) => "creator_denied",
+            FilterRejection::CreatorNotAllowed(_) => "creator_not_allowed",
+            FilterRejection::NameMismatch(_) => "name_mismatch",
+            FilterRejection::SymbolMismatch(_) => "symbol_mismatch",
+            FilterRejection::LookupFailed(_) => "lookup_failed",
+        }
+    }
+}
+
+impl fmt::Display for FilterRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.code())?;
+        match self {
+            FilterRejection::MarketCapTooLow { market_cap, min } => {
+                write!(f, "market cap {:.2} SOL below {} SOL", market_cap, min)
+            }
+            FilterRejection::MarketCapTooHigh { market_cap, max } => {
+                write!(f, "market cap {:.2} SOL above {} SOL", market_cap, max)
+            }
+            FilterRejection::CurveProgressTooHigh { progress, max } => {
+                write!(f, "curve {:.1}% complete, above {}%", progress, max)
+            }
+            FilterRejection::TokenTooNew { age, min } => write!(f, "token {:?} old, under {:?}", age, min),
+            FilterRejection::MintDenied => write!(f, "mint is deny-listed"),
+            FilterRejection::MintNotAllowed => write!(f, "mint is not allow-listed"),
+            FilterRejection::CreatorDenied(creator) => write!(f, "creator {} is deny-listed", creator),
+            FilterRejection::CreatorNotAllowed(creator) => write!(
+                f,
+                "creator {} is not allow-listed",
+                creator.as_deref().unwrap_or("unknown")
+            ),
+            FilterRejection::NameMismatch(name) => write!(f, "name {:?} doesn't match", name),
+            FilterRejection::SymbolMismatch(symbol) => write!(f, "symbol {:?} doesn't match", symbol),
+            FilterRejection::LookupFailed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// Checks a token before a copy buy. Every filter is optional and off unless
+// its `FILTER_*` variable is set.
+#[derive(Debug, Clone, Default)]
+pub struct EntryFilters {
+    pub min_market_cap: Option<f64>,
+    pub max_market_cap: Option<f64>,
+    pub max_curve_progress: Option<f64>,
+    pub min_token_age: Option<Duration>,
+    pub mint_allow: HashSet<String>,
+    pub mint_deny: HashSet<String>,
+    pub creator_allow: HashSet<String>,
+    pub creator_deny: HashSet<String>,
+    pub name_regex: Option<Regex>,
+    pub symbol_regex: Option<Regex>,
+}
+
+impl EntryFilters {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            min_market_cap: parse_env("FILTER_MIN_MARKET_CAP")?,
+            max_market_cap: parse_env("FILTER_MAX_MARKET_CAP")?,
+            max_curve_progress: parse_env("FILTER_MAX_CURVE_PROGRESS")?,
+            min_token_age: parse_env::<u64>("FILTER_MIN_TOKEN_AGE_SECS")?.map(Duration::from_secs),
+            mint_allow: parse_list("FILTER_MINT_ALLOW"),
+            mint_deny: parse_list("FILTER_MINT_DENY"),
+            creator_allow: parse_list("FILTER_CREATOR_ALLOW"),
+            creator_deny: parse_list("FILTER_CREATOR_DENY"),
+            name_regex: parse_regex("FILTER_NAME_REGEX")?,
+            symbol_regex: parse_regex("FILTER_SYMBOL_REGEX")?,
+        })
+    }
+
+    // Runs the cheap checks on `pump_info` first, then those needing RPC
+    // lookups.
+    pub async fn check(&self, client: &RpcClient, pump_info: &PumpInfo) -> Option<FilterRejection> {
+        match self.check_local(pump_info) {
+            Some(rejection) => Some(rejection),
+            None => self.check_rpc(client, &pump_info.mint).await,
+        }
+    }
+
+    // The checks that only need `pump_info`, which offline runs can apply.
+    pub fn check_local(&self, pump_info: &PumpInfo) -> Option<FilterRejection> {
+        let mint = &pump_info.mint;
+        if self.mint_deny.contains(mint) {
+            return Some(FilterRejection::MintDenied);
+        }
+        if !self.mint_allow.is_empty() && !self.mint_allow.contains(mint) {
+            return Some(FilterRejection::MintNotAllowed);
+        }
+        if let Some(creator) = pump_info.creator.as_ref().filter(|creator| self.creator_deny.contains(*creator)) {
+            return Some(FilterRejection::CreatorDenied(creator.clone()));
+        }
+        if !self.creator_allow.is_empty()
+            && !pump_info.creator.as_ref().is_some_and(|creator| self.creator_allow.contains(creator))
+        {
+            return Some(FilterRejection::CreatorNotAllowed(pump_info.creator.clone()));
+        }
+
+        let market_cap = get_market_cap(pump_info);
+        if let Some(min) = self.min_market_cap.filter(|min| market_cap < *min) {
+            return Some(FilterRejection::MarketCapTooLow { market_cap, min });
+        }
+        if let Some(max) = self.max_market_cap.filter(|max| market_cap > *max) {
+            return Some(FilterRejection::MarketCapTooHigh { market_cap, max });
+        }
+        let progress = get_curve_progress(pump_info);
+        if let Some(max) = self.max_curve_progress.filter(|max| progress > *max) {
+            return Some(FilterRejection::CurveProgressTooHigh { progress, max });
+        }
+        None
+    }
+
+    // Whether any filter needs RPC lookups, which offline runs can't apply.
+    pub fn needs_rpc(&self) -> bool {
+        self.name_regex.is_some() || self.symbol_regex.is_some() || self.min_token_age.is_some()
+    }
+
+    // Metadata and token age lookups. A lookup that fails rejects the token.
+    async fn check_rpc(&self, client: &RpcClient, mint: &str) -> Option<FilterRejection> {
+        let mint_pubkey = match Pubkey::from_str(mint) {
+            Ok(mint_pubkey) => mint_pubkey,
+            Err(e) => return Some(FilterRejection::LookupFailed(format!("invalid mint: {}", e))),
+        };
+        if self.name_regex.is_some() || self.symbol_regex.is_some() {
+            let metadata = match get_token_metadata(client, &mint_pubkey).await {
+                Ok(metadata) => metadata,
+                Err(e) => return Some(FilterRejection::LookupFailed(format!("metadata: {}", e))),
+            };
+            if self.name_regex.as_ref().is_some_and(|regex| !regex.is_match(&metadata.name)) {
+                return Some(FilterRejection::NameMismatch(metadata.name));
+            }
+            if self.symbol_regex.as_ref().is_some_and(|regex| !regex.is_match(&metadata.symbol)) {
+                return Some(FilterRejection::SymbolMismatch(metadata.symbol));
+            }
+        }
+        if let Some(min) = self.min_token_age {
+            match get_token_age(client, &mint_pubkey, min).await {
+                Ok(age) if age < min => return Some(FilterRejection::TokenTooNew { age, min }),
+                Ok(_) => {}
+                Err(e) => return Some(FilterRejection::LookupFailed(format!("token age: {}", e))),
+            }
+        }
+
+        None
+    }
+}
+
+// Age of the mint's oldest transaction. Pages back through its signatures only
+// until the creation is found or the age is known to be at least `enough`.
+async fn get_token_age(client: &RpcClient, mint: &Pubkey, enough: Duration) -> Result<Duration> {
+    let now = Utc::now().timestamp();
+    let mut before: Option<Signature> = None;
+
+    for _ in 0..MAX_SIGNATURE_PAGES {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until: None,
+            limit: Some(SIGNATURE_PAGE_LIMIT),
+            commitment: None,
+        };
+        let page = client.get_signatures_for_address_with_config(mint, config).await?;
+        let oldest = page.last().ok_or_else(|| anyhow!("no transactions for mint"))?;
+        let block_time = oldest
+            .block_time
+            .ok_or_else(|| anyhow!("no block time for {}", oldest.signature))?;
+        let age = Duration::from_secs(now.saturating_sub(block_time).max(0) as u64);
+
+        if page.len() < SIGNATURE_PAGE_LIMIT || age >= enough {
+            return Ok(age);
+        }
+        before = Some(Signature::from_str(&oldest.signature)?);
+    }
+
+    Err(anyhow!("mint has more than {} transactions", SIGNATURE_PAGE_LIMIT * MAX_SIGNATURE_PAGES))
+}
+
+fn parse_env<T: FromStr>(key: &str) -> Result<Option<T>>
+where
+    T::Err: fmt::Display,
+{
+    import_optional_env_var(key)
+        .map(|v| v.trim().parse::<T>().map_err(|e| anyhow!("Invalid {}: {}", key, e)))
+        .transpose()
+}
+
+fn parse_list(key: &str) -> HashSet<String> {
+    import_optional_env_var(key)
+        .map(|list| {
+            list.split(',')
+                .map(|entry| entry.trim().to_string())
+                .filter(|entry| !entry.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_regex(key: &str) -> Result<Option<Regex>> {
+    import_optional_env_var(key)
+        .map(|pattern| Regex::new(&pattern).map_err(|e| anyhow!("Invalid {}: {}", key, e)))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::dex::pump_fun::{
+            PUMP_INITIAL_REAL_TOKEN_RESERVES, PUMP_INITIAL_VIRTUAL_SOL_RESERVES, PUMP_INITIAL_VIRTUAL_TOKEN_RESERVES,
+            PUMP_TOKEN_TOTAL_SUPPLY,
+        },
+    };
+
+    // A curve with `sold` tokens bought out of a fresh one
+    fn pump_info(mint: &str, creator: Option<&str>, sold: u64) -> PumpInfo {
+        let virtual_token_reserves = PUMP_INITIAL_VIRTUAL_TOKEN_RESERVES - sold;
+        let virtual_sol_reserves = (PUMP_INITIAL_VIRTUAL_SOL_RESERVES as u128 * PUMP_INITIAL_VIRTUAL_TOKEN_RESERVES as u128
+            / virtual_token_reserves as u128) as u64;
+        PumpInfo {
+            mint: mint.to_string(),
+            bonding_curve: String::new(),
+            associated_bonding_curve: String::new(),
+            virtual_sol_reserves,
+            virtual_token_reserves,
+            real_sol_reserves: virtual_sol_reserves - PUMP_INITIAL_VIRTUAL_SOL_RESERVES,
+            real_token_reserves: PUMP_INITIAL_REAL_TOKEN_RESERVES - sold,
+            token_total_supply: PUMP_TOKEN_TOTAL_SUPPLY,
+            complete: false,
+            creator: creator.map(str::to_string),
+        }
+    }
+
+    fn set(entries: &[&str]) -> HashSet<String> {
+        entries.iter().map(|entry| entry.to_string()).collect()
+    }
+
+    #[test]
+    fn no_filters_pass_everything() {
+        assert_eq!(EntryFilters::default().check_local(&pump_info("mint", None, 0)), None);
+    }
+
+    #[test]
+    fn mint_and_creator_lists() {
+        let filters = EntryFilters {
+            mint_deny: set(&["bad"]),
+            creator_allow: set(&["dev"]),
+            ..EntryFilters::default()
+        };
+        assert_eq!(filters.check_local(&pump_info("bad", Some("dev"), 0)), Some(FilterRejection::MintDenied));
+        assert_eq!(filters.check_local(&pump_info("good", Some("dev"), 0)), None);
+        assert_eq!(
+            filters.check_local(&pump_info("good", Some("other"), 0)),
+            Some(FilterRejection::CreatorNotAllowed(Some("other".to_string())))
+        );
+        // An unknown creator can't be on the allow list
+        assert_eq!(
+            filters.check_local(&pump_info("good", None, 0)),
+            Some(FilterRejection::CreatorNotAllowed(None))
+        );
+
+        let filters = EntryFilters {
+            mint_allow: set(&["good"]),
+            creator_deny: set(&["rugger"]),
+            ..EntryFilters::default()
+        };
+        assert_eq!(filters.check_local(&pump_info("other", None, 0)), Some(FilterRejection::MintNotAllowed));
+        assert_eq!(
+            filters.check_local(&pump_info("good", Some("rugger"), 0)),
+            Some(FilterRejection::CreatorDenied("rugger".to_string()))
+        );
+        assert_eq!(filters.check_local(&pump_info("good", None, 0)), None);
+    }
+
+    #[test]
+    fn market_cap_bounds() {
+        // A fresh curve is worth about 28 SOL
+        let fresh = pump_info("mint", None, 0);
+        let filters = EntryFilters {
+            min_market_cap: Some(30.0),
+            ..EntryFilters::default()
+        };
+        assert!(matches!(
+            filters.check_local(&fresh),
+            Some(FilterRejection::MarketCapTooLow { min, .. }) if min == 30.0
+        ));
+        assert_eq!(filters.check_local(&pump_info("mint", None, 100_000_000_000_000)), None);
+
+        let filters = EntryFilters {
+            max_market_cap: Some(30.0),
+            ..EntryFilters::default()
+        };
+        assert_eq!(filters.check_local(&fresh), None);
+        assert!(matches!(
+            filters.check_local(&pump_info("mint", None, 100_000_000_000_000)),
+            Some(FilterRejection::MarketCapTooHigh { max, .. }) if max == 30.0
+        ));
+    }
+
+    #[test]
+    fn curve_progress_cap() {
+        let filters = EntryFilters {
+            max_curve_progress: Some(50.0),
+            ..EntryFilters::default()
+        };
+        assert_eq!(filters.check_local(&pump_info("mint", None, PUMP_INITIAL_REAL_TOKEN_RESERVES / 4)), None);
+        assert!(matches!(
+            filters.check_local(&pump_info("mint", None, PUMP_INITIAL_REAL_TOKEN_RESERVES * 3 / 4)),
+            Some(FilterRejection::CurveProgressTooHigh { progress, .. }) if (progress - 75.0).abs() < 1e-9
+        ));
+    }
+}
@@ -0,0 +1,165 @@
+use {
+    crate::{
+        common::utils::import_optional_env_var,
+        dex::pump_fun::{get_buy_price, get_max_buy_amount, PumpInfo, TradeEvent},
+    },
+    anyhow::{anyhow, Result},
+    std::fmt,
+};
+
+const DEFAULT_MIN_DOWNSIZE: u64 = 25; // percent of the intended amount
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrontRunDecision {
+    Proceed,
+    Downsize(u64),
+    Skip,
+}
+
+// Keeps copy buys from paying far more than the target did once other bots
+// have already pushed the curve up.
+#[derive(Debug, Clone)]
+pub struct FrontRunGuard {
+    pub max_premium: u64,
+    pub downsize: bool,
+    pub min_downsize: u64,
+}
+
+impl FrontRunGuard {
+    // Enabled by `FRONT_RUN_MAX_PREMIUM`. `FRONT_RUN_ACTION` is `downsize`
+    // (default) or `skip`.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(max_premium) = import_optional_env_var("FRONT_RUN_MAX_PREMIUM") else {
+            return Ok(None);
+        };
+        let max_premium = max_premium
+            .parse::<u64>()
+            .map_err(|e| anyhow!("Invalid FRONT_RUN_MAX_PREMIUM: {}", e))?;
+        let downsize = match import_optional_env_var("FRONT_RUN_ACTION") {
+            None => true,
+            Some(action) if action.eq_ignore_ascii_case("downsize") => true,
+            Some(action) if action.eq_ignore_ascii_case("skip") => false,
+            Some(action) => return Err(anyhow!("Invalid FRONT_RUN_ACTION: {} (expected downsize or skip)", action)),
+        };
+        let min_downsize = import_optional_env_var("FRONT_RUN_MIN_DOWNSIZE")
+            .map(|v| v.parse::<u64>().map_err(|e| anyhow!("Invalid FRONT_RUN_MIN_DOWNSIZE: {}", e)))
+            .transpose()?
+            .unwrap_or(DEFAULT_MIN_DOWNSIZE);
+
+        Ok(Some(Self {
+            max_premium,
+            downsize,
+            min_downsize,
+        }))
+    }
+
+    // Compares our quote for buying `amount` lamports against the target's fill.
+    pub fn check(&self, pump_info: &PumpInfo, target_trade: &TradeEvent, amount: u64) -> FrontRunDecision {
+        let (Some(target_price), Some(quote_price)) = (target_trade.price(), get_buy_price(pump_info, amount)) else {
+            return FrontRunDecision::Proceed;
+        };
+        let max_price = target_price * (100 + self.max_premium) as f64 / 100.0;
+        if quote_price <= max_price {
+            return FrontRunDecision::Proceed;
+        }
+        if !self.downsize {
+            return FrontRunDecision::Skip;
+        }
+
+        // Too small a remainder isn't worth the fees
+        let max_amount = get_max_buy_amount(pump_info, max_price).min(amount);
+        if max_amount == 0 || max_amount < amount * self.min_downsize / 100 {
+            FrontRunDecision::Skip
+        } else {
+            FrontRunDecision::Downsize(max_amount)
+        }
+    }
+}
+
+impl fmt::Display for FrontRunDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrontRunDecision::Proceed => write!(f, "proceed"),
+            FrontRunDecision::Downsize(amount) => write!(f, "downsize to {} lamports", amount),
+            FrontRunDecision::Skip => write!(f, "skip"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::dex::pump_fun::{
+            pump_info_from_trade, PUMP_INITIAL_VIRTUAL_SOL_RESERVES, PUMP_INITIAL_VIRTUAL_TOKEN_RESERVES,
+        },
+        solana_sdk::pubkey::Pubkey,
+    };
+
+    const TARGET_TOKENS: u64 = 1_000_000_000_000;
+
+    // A target buy of `TARGET_TOKENS` at `premium` times the fresh curve's
+    // spot price, and that fresh curve.
+    fn target_trade(premium: f64) -> (PumpInfo, TradeEvent) {
+        let spot = PUMP_INITIAL_VIRTUAL_SOL_RESERVES as f64 / PUMP_INITIAL_VIRTUAL_TOKEN_RESERVES as f64;
+        let trade = TradeEvent {
+            mint: Pubkey::new_unique(),
+            sol_amount: (spot * premium * TARGET_TOKENS as f64) as u64,
+            token_amount: TARGET_TOKENS,
+            is_buy: true,
+            user: Pubkey::new_unique(),
+            timestamp: 0,
+            virtual_sol_reserves: PUMP_INITIAL_VIRTUAL_SOL_RESERVES,
+            virtual_token_reserves: PUMP_INITIAL_VIRTUAL_TOKEN_RESERVES,
+        };
+        (pump_info_from_trade(&trade).unwrap(), trade)
+    }
+
+    fn guard(downsize: bool, min_downsize: u64) -> FrontRunGuard {
+        FrontRunGuard {
+            max_premium: 10,
+            downsize,
+            min_downsize,
+        }
+    }
+
+    #[test]
+    fn proceeds_within_the_premium() {
+        let (pump_info, trade) = target_trade(1.05);
+        assert_eq!(guard(true, 25).check(&pump_info, &trade, 100_000_000), FrontRunDecision::Proceed);
+    }
+
+    #[test]
+    fn downsizes_to_the_largest_buy_within_the_premium() {
+        // 10 SOL moves a fresh curve about 33% past its spot price
+        let (pump_info, trade) = target_trade(1.05);
+        let amount = 10_000_000_000;
+
+        let FrontRunDecision::Downsize(downsized) = guard(true, 25).check(&pump_info, &trade, amount) else {
+            panic!("expected a downsize");
+        };
+        assert!(downsized < amount && downsized > amount / 4);
+        let max_price = trade.price().unwrap() * 1.1;
+        assert!(get_buy_price(&pump_info, downsized).unwrap() <= max_price * (1.0 + 1e-9));
+        assert!(get_buy_price(&pump_info, downsized + 10_000_000).unwrap() > max_price);
+    }
+
+    #[test]
+    fn skips_when_configured_or_when_too_little_remains() {
+        let (pump_info, trade) = target_trade(1.05);
+        let amount = 10_000_000_000;
+        assert_eq!(guard(false, 25).check(&pump_info, &trade, amount), FrontRunDecision::Skip);
+        assert_eq!(guard(true, 60).check(&pump_info, &trade, amount), FrontRunDecision::Skip);
+
+        // Already past the premium before we buy anything
+        let (pump_info, trade) = target_trade(0.8);
+        assert_eq!(guard(true, 0).check(&pump_info, &trade, amount), FrontRunDecision::Skip);
+    }
+
+    #[test]
+    fn proceeds_without_a_target_price() {
+        let (pump_info, mut trade) = target_trade(1.05);
+        trade.token_amount = 0;
+        assert_eq!(guard(false, 25).check(&pump_info, &trade, 10_000_000_000), FrontRunDecision::Proceed);
+    }
+}
@@ -0,0 +1,310 @@
+use {
+    crate::{
+        common::{logger::Logger, utils::import_optional_env_var},
+        dex::pump_fun::{SimulationFailure, TradeEvent},
+        engine::{
+            executor::{pump_error, OnChainFailure, SwapResult},
+            paper::paper_mode,
+            positions::Fill,
+        },
+    },
+    anyhow::{anyhow, Result},
+    chrono::Utc,
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        fmt,
+        fs::{File, OpenOptions},
+        io::{BufRead, BufReader, Write},
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::{Arc, Mutex},
+    },
+};
+
+const DEFAULT_JOURNAL_PATH: &str = "journal.jsonl";
+const DEFAULT_PAPER_JOURNAL_PATH: &str = "journal.paper.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    // Sent and landed
+    Copied,
+    // Sent, but no variant landed before the blockhash expired
+    NotLanded,
+    // Deliberately not sent; `skip_reason` says why
+    Skipped,
+    // Tried and failed, on-chain or before sending
+    Failed,
+}
+
+// The target's decoded TradeEvent, with keys as strings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalTrade {
+    pub mint: String,
+    pub user: String,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    pub timestamp: i64,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+}
+
+impl From<&TradeEvent> for JournalTrade {
+    fn from(trade: &TradeEvent) -> Self {
+        Self {
+            mint: trade.mint.to_string(),
+            user: trade.user.to_string(),
+            sol_amount: trade.sol_amount,
+            token_amount: trade.token_amount,
+            is_buy: trade.is_buy,
+            timestamp: trade.timestamp,
+            virtual_sol_reserves: trade.virtual_sol_reserves,
+            virtual_token_reserves: trade.virtual_token_reserves,
+        }
+    }
+}
+
+impl TryFrom<&JournalTrade> for TradeEvent {
+    type Error = anyhow::Error;
+
+    fn try_from(trade: &JournalTrade) -> Result<Self> {
+        Ok(Self {
+            mint: Pubkey::from_str(&trade.mint)?,
+            sol_amount: trade.sol_amount,
+            token_amount: trade.token_amount,
+            is_buy: trade.is_buy,
+            user: Pubkey::from_str(&trade.user)?,
+            timestamp: trade.timestamp,
+            virtual_sol_reserves: trade.virtual_sol_reserves,
+            virtual_token_reserves: trade.virtual_token_reserves,
+        })
+    }
+}
+
+impl JournalTrade {
+    pub fn price(&self) -> Option<f64> {
+        if self.token_amount == 0 {
+            return None;
+        }
+        Some(self.sol_amount as f64 / self.token_amount as f64)
+    }
+}
+
+// Milliseconds spent in each stage of handling a signal.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Latency {
+    // From the target's block time to us picking the signal up
+    pub detection_ms: Option<i64>,
+    // Fetching the curve and running the checks
+    pub decision_ms: Option<u64>,
+    // Building, sending and confirming our swap
+    pub execution_ms: Option<u64>,
+    // Send ack from the path that landed
+    pub ack_ms: Option<u64>,
+    pub total_ms: Option<u64>,
+}
+
+// One line of the journal: a target signal or exit trigger and what came of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalRecord {
+    pub timestamp: String,
+    // "copy" for target signals, "exit" for the exit manager's own sells and
+    // "bundle" for a Jito bundle outcome that arrived after its swap was
+    // journaled
+    pub source: String,
+    pub mint: String,
+    pub is_buy: bool,
+    pub target_wallet: Option<String>,
+    pub target_signature: Option<String>,
+    pub target_slot: Option<u64>,
+    pub trade: Option<JournalTrade>,
+    // What triggered an exit manager sell
+    pub exit_reason: Option<String>,
+    pub decision: Decision,
+    pub skip_reason: Option<String>,
+    pub detail: Option<String>,
+    pub signature: Option<String>,
+    pub send_path: Option<String>,
+    pub landed_slot: Option<u64>,
+    pub slot_delta: Option<i64>,
+    pub bundle_outcome: Option<String>,
+    pub pump_error: Option<String>,
+    pub fill: Option<Fill>,
+    pub latency: Latency,
+}
+
+impl JournalRecord {
+    pub fn new(source: &str, mint: &str, is_buy: bool) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            source: source.to_string(),
+            mint: mint.to_string(),
+            is_buy,
+            target_wallet: None,
+            target_signature: None,
+            target_slot: None,
+            trade: None,
+            exit_reason: None,
+            decision: Decision::Skipped,
+            skip_reason: None,
+            detail: None,
+            signature: None,
+            send_path: None,
+            landed_slot: None,
+            slot_delta: None,
+            bundle_outcome: None,
+            pump_error: None,
+            fill: None,
+            latency: Latency::default(),
+        }
+    }
+
+    pub fn skipped(mut self, reason: &str, detail: impl fmt::Display) -> Self {
+        self.decision = Decision::Skipped;
+        self.skip_reason = Some(reason.to_string());
+        self.detail = Some(detail.to_string());
+        self
+    }
+
+    // Fills in the outcome of a swap attempt.
+    pub fn with_swap(mut self, result: &Result<SwapResult>) -> Self {
+        match result {
+            Ok(swap_result) => {
+                self.decision = if swap_result.landed() { Decision::Copied } else { Decision::NotLanded };
+                self.signature = Some(swap_result.signature.clone());
+                self.send_path = swap_result.landed_path.as_ref().map(|path| path.to_string());
+                self.landed_slot = swap_result.landed_slot;
+                self.slot_delta = swap_result.slot_delta;
+                self.bundle_outcome = swap_result.bundle_outcome().map(|outcome| format!("{:?}", outcome));
+                self.latency.ack_ms = swap_result
+                    .paths
+                    .iter()
+                    .find(|path| Some(&path.path) == swap_result.landed_path.as_ref())
+                    .map(|path| path.ack_latency.as_millis() as u64);
+            }
+            Err(e) => {
+                let error = pump_error(e);
+                self.pump_error = error.map(|error| error.as_str().to_string());
+                self.detail = Some(e.to_string());
+                if let Some(skip) = e.downcast_ref::<Skip>() {
+                    self.decision = Decision::Skipped;
+                    self.skip_reason = Some(skip.reason.to_string());
+                } else if e.downcast_ref::<SimulationFailure>().is_some() {
+                    // Caught before sending, so nothing was spent
+                    self.decision = Decision::Skipped;
+                    self.skip_reason = Some(
+                        error
+                            .map(|error| error.as_str().to_string())
+                            .unwrap_or_else(|| "simulation_failed".to_string()),
+                    );
+                } else if let Some(failure) = e.downcast_ref::<OnChainFailure>() {
+                    self.decision = Decision::Failed;
+                    self.signature = Some(failure.signature.clone());
+                    self.landed_slot = Some(failure.slot);
+                } else if let Some(error) = error {
+                    // Rejected while building, e.g. a completed bonding curve
+                    self.decision = Decision::Skipped;
+                    self.skip_reason = Some(error.as_str().to_string());
+                } else {
+                    self.decision = Decision::Failed;
+                }
+            }
+        }
+        self
+    }
+}
+
+// A deliberate decision not to send, with a stable reason code.
+#[derive(Debug, Clone)]
+pub struct Skip {
+    pub reason: &'static str,
+    pub detail: String,
+}
+
+impl Skip {
+    pub fn new(reason: &'static str, detail: impl fmt::Display) -> Self {
+        Self {
+            reason,
+            detail: detail.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Skip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.reason, self.detail)
+    }
+}
+
+impl std::error::Error for Skip {}
+
+// Append-only JSONL log of every signal and action, at `JOURNAL_PATH`.
+#[derive(Clone)]
+pub struct Journal {
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+}
+
+impl Journal {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| anyhow!("Failed to open journal {}: {}", path.display(), e))?;
+
+        Ok(Self {
+            path,
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    pub fn from_env() -> Result<Self> {
+        Self::open(journal_path())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Failing to journal never stops trading, so errors are only logged.
+    pub fn append(&self, record: &JournalRecord) {
+        let logger = Logger::new("[JOURNAL]".to_string());
+        let written = serde_json::to_string(record)
+            .map_err(|e| anyhow!(e))
+            .and_then(|line| {
+                let mut file = self.file.lock().map_err(|e| anyhow!("{}", e))?;
+                writeln!(file, "{}", line)?;
+                file.flush()?;
+                Ok(())
+            });
+        if let Err(e) = written {
+            logger.error(format!("Failed to write to {}: {}", self.path.display(), e));
+        }
+    }
+}
+
+// Paper trading keeps its own journal unless `JOURNAL_PATH` says otherwise.
+pub fn journal_path() -> PathBuf {
+    let default = if paper_mode() { DEFAULT_PAPER_JOURNAL_PATH } else { DEFAULT_JOURNAL_PATH };
+    PathBuf::from(import_optional_env_var("JOURNAL_PATH").unwrap_or_else(|| default.to_string()))
+}
+
+// Reads every record of a journal. Lines that don't parse, such as one cut
+// short by a crash, are skipped.
+pub fn read_journal(path: impl AsRef<Path>) -> Result<Vec<JournalRecord>> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| anyhow!("Failed to open journal {}: {}", path.display(), e))?;
+
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if let Ok(record) = serde_json::from_str::<JournalRecord>(&line) {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
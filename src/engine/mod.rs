@@ -1 +1,16 @@
+pub mod backtest;
+pub mod confirmation;
+pub mod dispatcher;
+pub mod executor;
+pub mod exit;
+pub mod filters;
+pub mod front_run;
+pub mod journal;
 pub mod monitor;
+pub mod paper;
+pub mod positions;
+pub mod reconcile;
+pub mod report;
+pub mod sizing;
+pub mod slippage;
+pub mod staleness;
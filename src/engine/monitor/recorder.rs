@@ -0,0 +1,73 @@
+use {
+    crate::common::{logger::Logger, utils::import_optional_env_var},
+    anyhow::{anyhow, Result},
+    solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta,
+    std::{
+        fs,
+        path::{Path, PathBuf},
+    },
+};
+
+const EXPECTED_SUFFIX: &str = ".expected.json";
+
+// Saves every target transaction the monitor processes, as returned by
+// getTransaction, to `RECORD_DIR/<signature>.json` for the replay harness.
+#[derive(Debug, Clone)]
+pub struct TransactionRecorder {
+    dir: PathBuf,
+}
+
+impl TransactionRecorder {
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(dir) = import_optional_env_var("RECORD_DIR") else {
+            return Ok(None);
+        };
+        let dir = PathBuf::from(dir);
+        fs::create_dir_all(&dir).map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+        Ok(Some(Self { dir }))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // Failing to record never stops copying, so errors are only logged.
+    pub fn record(&self, signature: &str, transaction: &EncodedConfirmedTransactionWithStatusMeta) {
+        let path = self.dir.join(format!("{}.json", signature));
+        let written = serde_json::to_string_pretty(transaction)
+            .map_err(|e| anyhow!(e))
+            .and_then(|json| fs::write(&path, json).map_err(|e| anyhow!(e)));
+        if let Err(e) = written {
+            Logger::new("[RECORDER]".to_string()).error(format!("Failed to write {}: {}", path.display(), e));
+        }
+    }
+}
+
+pub fn read_recorded(path: impl AsRef<Path>) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+    let path = path.as_ref();
+    let json = fs::read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&json).map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))
+}
+
+// Recorded transactions under `path`, a single file or a directory, in name
+// order so replays are deterministic.
+pub fn recorded_files(path: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|file| file.extension().is_some_and(|extension| extension == "json"))
+        .filter(|file| !file.to_string_lossy().ends_with(EXPECTED_SUFFIX))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// Where the replay harness keeps the expected decoding of `recorded`.
+pub fn expected_path(recorded: &Path) -> PathBuf {
+    let stem = recorded.file_stem().unwrap_or_default().to_string_lossy();
+    recorded.with_file_name(format!("{}{}", stem, EXPECTED_SUFFIX))
+}
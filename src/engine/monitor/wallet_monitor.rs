@@ -1,9 +1,29 @@
 use {
     crate::{
-        common::{logger::Logger, utils::AppState},
-        dex::pump_fun::{Pump, PumpInfo, get_pump_info},
+        common::{logger::Logger, utils::{AppState, SwapConfig, SwapDirection}},
+        dex::pump_fun::{
+            Pump, PumpInfo, SimulationFailure, TradeEvent, decode_trade_event, decode_trade_event_instruction,
+            get_buy_price, get_price_after_buy, get_pump_info,
+        },
+        dex::pump_error::PumpError,
+        engine::{
+            dispatcher::MintDispatcher,
+            exit::ExitManager,
+            filters::EntryFilters,
+            positions::PositionBook,
+            executor::{pump_error, Executor, SwapResult},
+            front_run::{FrontRunDecision, FrontRunGuard},
+            journal::{Journal, JournalRecord, JournalTrade, Skip},
+            monitor::recorder::TransactionRecorder,
+            paper::{self, PaperPortfolio},
+            sizing::{copy_buy_amount, copy_sell_amount},
+            slippage::SlippageRetryPolicy,
+            staleness::StalenessFilter,
+        },
+        services::jito::TipConfig,
     },
     anyhow::{anyhow, Result},
+    futures::future::join_all,
     solana_client::rpc_config::RpcTransactionConfig,
     solana_sdk::{
         commitment_config::CommitmentConfig,
@@ -14,13 +34,13 @@ use {
         EncodedTransaction, 
         UiTransactionEncoding,
         EncodedTransactionWithStatusMeta,
+        UiInstruction,
+        UiMessage,
         option_serializer::OptionSerializer,
     },
     std::{str::FromStr, time::{Duration, Instant}},
     tokio::time,
     chrono::Utc,
-    base64,
-    bs58,
 };
 
 const RETRY_DELAY: u64 = 5; // seconds
@@ -28,9 +48,25 @@ const MONITOR_INTERVAL: u64 = 2; // seconds
 const TARGET_WALLET: &str = "o7RY6P2vQMuGSu1TrLM81weuzgDjaCRTXYRaXJwWcvc";
 const PUMP_PROGRAM_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
 
+// Long-lived pieces the copy pipeline shares across signals.
+#[derive(Clone)]
+pub struct CopyContext {
+    pub exit_manager: ExitManager,
+    pub filters: EntryFilters,
+    pub positions: PositionBook,
+    pub journal: Journal,
+    pub recorder: Option<TransactionRecorder>,
+    pub dispatcher: MintDispatcher,
+    pub staleness: Option<StalenessFilter>,
+    pub front_run: Option<FrontRunGuard>,
+    pub retry_policy: Option<SlippageRetryPolicy>,
+    pub paper: Option<PaperPortfolio>,
+}
+
 pub async fn monitor_wallet(
     ws_url: &str,
     state: AppState,
+    context: CopyContext,
     slippage: u64,
     use_jito: bool,
 ) -> Result<()> {
@@ -49,9 +85,9 @@ pub async fn monitor_wallet(
          * [Monitor Interval]: {}s, * [Retry Delay]: {}s
          ",
         ws_url,
-        target_wallet.to_string(),
+        target_wallet,
         state.wallet.pubkey(),
-        state.rpc_client.get_balance(&state.wallet.pubkey())? as f64 / 1_000_000_000.0,
+        state.rpc_nonblocking_client.get_balance(&state.wallet.pubkey()).await? as f64 / 1_000_000_000.0,
         slippage,
         use_jito,
         MONITOR_INTERVAL,
@@ -74,7 +110,7 @@ pub async fn monitor_wallet(
         ));
 
         // Monitor transactions
-        match monitor_transactions(&state, &target_wallet, last_signature).await {
+        match monitor_transactions(&state, &context, &target_wallet, last_signature, slippage, use_jito).await {
             Ok((count, latest_sig)) => {
                 if count > 0 {
                     logger.transaction(format!(
@@ -91,14 +127,25 @@ pub async fn monitor_wallet(
         }
 
         // Monitor balances
-        if let Ok(target_balance) = state.rpc_client.get_balance(&target_wallet) {
-            if let Ok(bot_balance) = state.rpc_client.get_balance(&state.wallet.pubkey()) {
-                logger.info(format!(
-                    "[BALANCES] => Target: {} SOL, Bot: {} SOL",
-                    target_balance as f64 / 1_000_000_000.0,
-                    bot_balance as f64 / 1_000_000_000.0
-                ));
-            }
+        let bot_wallet = state.wallet.pubkey();
+        if let (Ok(target_balance), Ok(bot_balance)) = tokio::join!(
+            state.rpc_nonblocking_client.get_balance(&target_wallet),
+            state.rpc_nonblocking_client.get_balance(&bot_wallet),
+        ) {
+            logger.info(format!(
+                "[BALANCES] => Target: {} SOL, Bot: {} SOL",
+                target_balance as f64 / 1_000_000_000.0,
+                bot_balance as f64 / 1_000_000_000.0
+            ));
+        }
+
+        let in_flight = context.dispatcher.in_flight();
+        if in_flight > 0 {
+            logger.info(format!("[IN FLIGHT] => Copies running on {} mints", in_flight));
+        }
+        context.positions.log_summary();
+        if let Some(paper) = &context.paper {
+            paper.log_summary();
         }
 
         // Log cycle completion
@@ -111,8 +158,11 @@ pub async fn monitor_wallet(
 
 async fn monitor_transactions(
     state: &AppState, 
+    context: &CopyContext,
     target_wallet: &Pubkey,
-    last_sig: Option<Signature>
+    last_sig: Option<Signature>,
+    slippage: u64,
+    use_jito: bool,
 ) -> Result<(u64, Option<Signature>)> {
     let logger = Logger::new("[TX MONITOR]".to_string());
     let start_time = Instant::now();
@@ -123,24 +173,20 @@ async fn monitor_transactions(
         max_supported_transaction_version: Some(0),
     };
 
-    let signatures = state.rpc_client.get_signatures_for_address(target_wallet)?;
-    let mut tx_count = 0;
-    let mut latest_signature = None;
+    let signatures = state
+        .rpc_nonblocking_client
+        .get_signatures_for_address(target_wallet)
+        .await?;
 
+    // Successful signatures since the last one seen, newest first
+    let mut new_signatures = Vec::new();
     for sig in signatures.iter().take(5) {
         if sig.err.is_none() {
             let signature = Signature::from_str(&sig.signature)?;
             
             // Skip if we've seen this transaction
-            if let Some(last_seen) = last_sig {
-                if signature == last_seen {
-                    break;
-                }
-            }
-
-            // Update latest signature
-            if latest_signature.is_none() {
-                latest_signature = Some(signature);
+            if last_sig == Some(signature) {
+                break;
             }
 
             // Log new transaction detection
@@ -148,126 +194,235 @@ async fn monitor_transactions(
                 "\n   * [NEW TX] => (\"{}\") - SLOT:({}) \n   * [FROM] => ({}) \n   * [TIME] => {} :: ({:?}).",
                 sig.signature,
                 sig.slot,
-                target_wallet.to_string(),
+                target_wallet,
                 Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
                 start_time.elapsed()
             ));
+            new_signatures.push((sig, signature));
+        }
+    }
+    let latest_signature = new_signatures.first().map(|(_, signature)| *signature);
 
-            if let Ok(tx_response) = state.rpc_client.get_transaction_with_config(&signature, config.clone()) {
-                tx_count += 1;
-                
-                // Process transaction
-                if let Ok(()) = process_transaction(&state, &tx_response.transaction).await {
-                    logger.success(format!(
-                        "\n   * [COPYING TX] => Hash: (\"{}\") \n   * [SLOT] => ({}) \n   * [TIME] => {} :: ({:?}).",
-                        sig.signature,
-                        tx_response.slot,
-                        Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
-                        start_time.elapsed()
-                    ));
+    // Fetch all new transactions at once instead of one round trip each
+    let responses = join_all(
+        new_signatures
+            .iter()
+            .map(|(_, signature)| state.rpc_nonblocking_client.get_transaction_with_config(signature, config)),
+    )
+    .await;
 
-                    // Copy the transaction
-                    if let Err(e) = copy_transaction(&state, &tx_response.transaction).await {
-                        logger.error(format!("Failed to copy transaction: {}", e));
-                    }
-                }
+    // Oldest first, so the target's trades on a mint are copied in order
+    let mut tx_count = 0;
+    for ((sig, _), response) in new_signatures.iter().zip(responses).rev() {
+        let tx_response = match response {
+            Ok(tx_response) => tx_response,
+            Err(e) => {
+                logger.warning(format!("Failed to fetch {}: {}", sig.signature, e));
+                continue;
             }
+        };
+        tx_count += 1;
+        if let Some(recorder) = &context.recorder {
+            recorder.record(&sig.signature, &tx_response);
+        }
+        
+        // Process transaction
+        if let Ok(Some(trade)) = process_transaction(&tx_response.transaction).await {
+            logger.success(format!(
+                "\n   * [COPYING TX] => Hash: (\"{}\") \n   * [SLOT] => ({}) \n   * [TIME] => {} :: ({:?}).",
+                sig.signature,
+                tx_response.slot,
+                Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+                start_time.elapsed()
+            ));
+
+            // Copy the transaction without holding up other mints
+            let (state, task_context) = (state.clone(), context.clone());
+            context.dispatcher.dispatch(&trade.mint.to_string(), async move {
+                if let Err(e) = copy_transaction(&state, &task_context, &tx_response.transaction, tx_response.slot, tx_response.block_time, slippage, use_jito).await {
+                    Logger::new("[TX MONITOR]".to_string()).error(format!("Failed to copy transaction: {}", e));
+                }
+            })?;
         }
     }
 
     Ok((tx_count, latest_signature))
 }
 
-async fn process_transaction(state: &AppState, transaction: &EncodedTransactionWithStatusMeta) -> Result<()> {
+// The target's Pump trade in `transaction`, or `None` if it didn't touch Pump.
+pub async fn process_transaction(transaction: &EncodedTransactionWithStatusMeta) -> Result<Option<TradeEvent>> {
     let logger = Logger::new("[PROCESS TX]".to_string());
     
-    if let EncodedTransaction::Json(tx_data) = &transaction.transaction {
+    if let EncodedTransaction::Json(_) = &transaction.transaction {
         if let Some(meta) = &transaction.meta {
-            if let OptionSerializer::Some(logs) = &meta.log_messages {
-                if logs.iter().any(|log| log.contains(PUMP_PROGRAM_ID)) {
-                    logger.success("Found PumpFun transaction!".to_string());
-                    
-                    // Extract transaction data
-                    let program_data = extract_program_data(logs)?;
-                    let trade_info = parse_trade_info(&program_data)?;
-                    
-                    // Log transaction details
-                    logger.info(format!(
-                        "\n   * [PUMP TRANSACTION FOUND] => \n   * [LOGS] => {:?}\n   * [TRADE INFO] => {:?}",
-                        logs, trade_info
-                    ));
-                    
-                    return Ok(());
-                }
+            let logs = match &meta.log_messages {
+                OptionSerializer::Some(logs) => logs.as_slice(),
+                _ => &[],
+            };
+            // Truncated logs may not reach the Pump instruction; the account
+            // keys always list the program
+            if logs.iter().any(|log| log.contains(PUMP_PROGRAM_ID))
+                || account_keys(transaction).iter().any(|key| key == PUMP_PROGRAM_ID)
+            {
+                logger.success("Found PumpFun transaction!".to_string());
+                
+                // Extract transaction data
+                let trade_info = parse_trade_info(transaction)?;
+                
+                // Log transaction details
+                logger.info(format!(
+                    "\n   * [PUMP TRANSACTION FOUND] => \n   * [LOGS] => {:?}\n   * [TRADE INFO] => {:?}",
+                    logs, trade_info
+                ));
+                
+                return Ok(Some(trade_info));
             }
         }
     }
     
-    Ok(())
+    Ok(None)
 }
 
-async fn copy_transaction(state: &AppState, transaction: &EncodedTransactionWithStatusMeta) -> Result<()> {
+async fn copy_transaction(
+    state: &AppState,
+    context: &CopyContext,
+    transaction: &EncodedTransactionWithStatusMeta,
+    target_slot: u64,
+    block_time: Option<i64>,
+    slippage: u64,
+    use_jito: bool,
+) -> Result<()> {
     let logger = Logger::new("[COPY TX]".to_string());
     let start_time = Instant::now();
 
     if let EncodedTransaction::Json(tx_data) = &transaction.transaction {
-        if let Some(meta) = &transaction.meta {
-            if let OptionSerializer::Some(logs) = &meta.log_messages {
-                // Extract mint address and instruction type
-                let (mint, is_buy) = extract_transaction_info(logs)?;
-                
+        // Extract mint address and instruction type
+        let (mint, is_buy) = extract_transaction_info(transaction)?;
+        // The target's own fill, to bound retries against
+        let target_trade = parse_trade_info(transaction).ok();
+        if !is_buy {
+            context.exit_manager.record_target_sell(&mint);
+        }
+
+        let mut record = JournalRecord::new("copy", &mint, is_buy);
+        record.target_wallet = Some(TARGET_WALLET.to_string());
+        record.target_signature = tx_data.signatures.first().cloned();
+        record.target_slot = Some(target_slot);
+        record.trade = target_trade.as_ref().map(JournalTrade::from);
+        record.latency.detection_ms =
+            block_time.map(|block_time| Utc::now().timestamp_millis() - block_time * 1000);
+        
+        logger.info(format!(
+            "\n   * [BUILD-IXN]({}) - {} :: {:?}",
+            mint, 
+            Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            start_time.elapsed()
+        ));
+
+        // Create Pump instance
+        let pump = Pump::new(
+            state.rpc_nonblocking_client.clone(),
+            state.wallet.clone(),
+            state.blockhash_cache.clone(),
+            state.nonce.clone(),
+            state.lookup_table.clone(),
+        );
+
+        // Get pump info
+        match get_pump_info(state.rpc_nonblocking_client.clone(), &mint).await {
+            Ok(pump_info) => {
                 logger.info(format!(
-                    "\n   * [BUILD-IXN]({}) - {} :: {:?}",
-                    mint, 
+                    "\n   * [SWAP-BEGIN]({}) - {} :: {:?}",
+                    mint,
                     Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
                     start_time.elapsed()
                 ));
 
-                // Create Pump instance
-                let pump = Pump::new(
-                    state.rpc_nonblocking_client.clone(),
-                    state.wallet.clone(),
-                );
-
-                // Get pump info
-                match get_pump_info(state.rpc_client.clone(), &mint).await {
-                    Ok(pump_info) => {
-                        logger.info(format!(
-                            "\n   * [SWAP-BEGIN]({}) - {} :: {:?}",
-                            mint,
-                            Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
-                            start_time.elapsed()
-                        ));
+                logger.info(format!(
+                    "\n   * [PUMP-INFO] => {:?}",
+                    pump_info
+                ));
 
-                        logger.info(format!(
-                            "\n   * [PUMP-INFO] => {:?}",
-                            pump_info
-                        ));
+                // Drop buys that aged out while we fetched; a late sell still beats holding
+                if let Some(filter) = context.staleness.as_ref().filter(|_| is_buy) {
+                    let current_slot = match filter.max_slots {
+                        Some(_) => state
+                            .rpc_nonblocking_client
+                            .get_slot_with_commitment(CommitmentConfig::processed())
+                            .await
+                            .ok(),
+                        None => None,
+                    };
+                    if let Some(stale) = filter.check(target_slot, block_time, current_slot) {
+                        logger.warning(format!("[SKIP] => {} - Stale signal: {}", mint, stale));
+                        context.journal.append(&record.skipped("stale_signal", stale));
+                        return Ok(());
+                    }
+                }
 
-                        // Execute swap
-                        match execute_swap(&pump, &mint, is_buy, &pump_info).await {
-                            Ok(signature) => {
-                                logger.success(format!(
-                                    "\n   * [SUCCESSFUL-{}] => TX_HASH: (\"{}\") \n   * [POOL] => ({}) \n   * [COPIED] => {} :: ({:?}).",
-                                    if is_buy { "BUY" } else { "SELL" },
-                                    signature,
-                                    mint,
-                                    Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
-                                    start_time.elapsed()
-                                ));
-                            }
+                if is_buy {
+                    if let Some(rejection) = context.filters.check(&state.rpc_nonblocking_client, &pump_info).await {
+                        logger.warning(format!("[SKIP] => {} - Filtered: {}", mint, rejection));
+                        context.journal.append(&record.skipped(rejection.code(), rejection));
+                        return Ok(());
+                    }
+                }
+                record.latency.decision_ms = Some(start_time.elapsed().as_millis() as u64);
+
+                // Execute swap
+                let execution_start = Instant::now();
+                let result = execute_swap(&pump, context, is_buy, &pump_info, target_trade.as_ref(), target_slot, slippage, use_jito, &state.tip_config).await;
+                record.latency.execution_ms = Some(execution_start.elapsed().as_millis() as u64);
+                record = record.with_swap(&result);
+
+                match result {
+                    Ok(swap_result) if swap_result.landed() => {
+                        match context
+                            .positions
+                            .record_fill(&state.rpc_nonblocking_client, &state.wallet.pubkey(), &mint, &swap_result)
+                            .await
+                        {
+                            Ok((fill, _)) => record.fill = Some(fill),
                             Err(e) => {
-                                logger.error(format!("Failed to execute swap: {}", e));
+                                logger.error(format!("Failed to record fill of {}: {}", swap_result.signature, e));
                             }
                         }
+                        if is_buy {
+                            let entry = record.fill.as_ref().and_then(|fill| {
+                                get_price_after_buy(&pump_info, fill.token_amount)
+                                    .map(|price| (price, fill.token_amount))
+                            });
+                            context.exit_manager.register(&pump_info, entry);
+                        }
+                        logger.success(format!(
+                            "\n   * [SUCCESSFUL-{}] => TX_HASH: (\"{}\") \n   * [POOL] => ({}) \n   * [COPIED] => {} :: ({:?}).",
+                            if is_buy { "BUY" } else { "SELL" },
+                            swap_result.signature,
+                            mint,
+                            Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+                            start_time.elapsed()
+                        ));
                     }
-                    Err(e) => {
+                    Ok(swap_result) => {
                         logger.error(format!(
-                            "Skip {} by Failed to get bonding curve account data: {}", 
-                            mint, e
+                            "Copy of {} not landed: {} (bundle {:?})",
+                            mint, swap_result.signature, swap_result.bundle_outcome()
                         ));
                     }
+                    Err(e) => {
+                        logger.error(format!("Failed to execute swap: {}", e));
+                    }
                 }
+
+                record.latency.total_ms = Some(start_time.elapsed().as_millis() as u64);
+                context.journal.append(&record);
+            }
+            Err(e) => {
+                logger.error(format!(
+                    "Skip {} by Failed to get bonding curve account data: {}", 
+                    mint, e
+                ));
+                context.journal.append(&record.skipped("bonding_curve_unavailable", e));
             }
         }
     }
@@ -276,108 +431,122 @@ async fn copy_transaction(state: &AppState, transaction: &EncodedTransactionWith
 }
 
 // Helper functions
-fn extract_program_data(logs: &[String]) -> Result<String> {
-    for log in logs {
-        if log.starts_with("Program data: ") {
-            return Ok(log.trim_start_matches("Program data: ").to_string());
-        }
+// First Pump TradeEvent of the transaction, from the "Program data:" lines of
+// the logs or, when those were truncated, from Pump's event self-CPI among
+// the inner instructions.
+fn parse_trade_info(transaction: &EncodedTransactionWithStatusMeta) -> Result<TradeEvent> {
+    let meta = transaction
+        .meta
+        .as_ref()
+        .ok_or_else(|| anyhow!("Transaction has no meta"))?;
+    let from_logs = match &meta.log_messages {
+        OptionSerializer::Some(logs) => logs
+            .iter()
+            .filter_map(|log| log.strip_prefix("Program data: "))
+            .find_map(decode_trade_event),
+        _ => None,
+    };
+    if let Some(trade) = from_logs {
+        return Ok(trade);
     }
-    Err(anyhow!("No program data found in logs"))
-}
-
-fn parse_trade_info(program_data: &str) -> Result<TradeInfo> {
-    // TODO: Implement proper trade info parsing from program data
-    Ok(TradeInfo {
-        mint: String::new(),
-        amount: 0,
-        is_buy: false,
-    })
-}
 
-fn extract_transaction_info(logs: &[String]) -> Result<(String, bool)> {
-    let mut mint_address = String::new();
-    let mut is_buy = false;
-    
-    for log in logs {
-        // Extract and analyze program data
-        if log.starts_with("Program data: ") {
-            let data = log.trim_start_matches("Program data: ");
-            if let Ok(decoded) = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, data) {
-                println!("Decoded data length: {}", decoded.len());
-                
-                // First 8 bytes are instruction discriminator
-                if decoded.len() >= 8 {
-                    let discriminator = &decoded[0..8];
-                    let discriminator_value = u64::from_le_bytes(discriminator.try_into().unwrap());
-                    println!("Instruction discriminator: {}", discriminator_value);
-
-                    // Match against known method discriminators
-                    is_buy = match discriminator_value {
-                        16927863322537952870 => true,  // PUMP_BUY_METHOD
-                        12502976635542562355 => false, // PUMP_SELL_METHOD
-                        _ => {
-                            println!("Unknown instruction discriminator");
-                            false
-                        }
-                    };
-                }
-
-                // Next 32 bytes after instruction data should be the mint address
-                if decoded.len() >= 40 {  // 8 (discriminator) + 32 (mint)
-                    let mint_bytes = &decoded[8..40];
-                    mint_address = bs58::encode(mint_bytes).into_string();
-                    println!("Extracted mint address: {}", mint_address);
-                }
-
-                // Analyze remaining data (amount, etc)
-                if decoded.len() > 40 {
-                    let amount_bytes = &decoded[40..48]; // Next 8 bytes for amount
-                    if amount_bytes.len() == 8 {
-                        let amount = u64::from_le_bytes(amount_bytes.try_into().unwrap());
-                        println!("Transaction amount: {}", amount);
-                    }
-                }
-            } else {
-                println!("Failed to decode base64 data: {}", data);
+    let OptionSerializer::Some(inner_instructions) = &meta.inner_instructions else {
+        return Err(anyhow!("No Pump TradeEvent found in logs"));
+    };
+    let account_keys = account_keys(transaction);
+    inner_instructions
+        .iter()
+        .flat_map(|inner| &inner.instructions)
+        .find_map(|instruction| match instruction {
+            UiInstruction::Compiled(instruction)
+                if account_keys.get(instruction.program_id_index as usize).map(String::as_str)
+                    == Some(PUMP_PROGRAM_ID) =>
+            {
+                decode_trade_event_instruction(&bs58::decode(&instruction.data).into_vec().ok()?)
             }
-        }
-        
-        // Additional log analysis for confirmation
-        if log.contains("Program log: Instruction: Buy") {
-            println!("Confirmed Buy instruction from logs");
-            is_buy = true;
-        } else if log.contains("Program log: Instruction: Sell") {
-            println!("Confirmed Sell instruction from logs");
-            is_buy = false;
-        }
-    }
-
-    if mint_address.is_empty() {
-        return Err(anyhow!("Could not extract mint address from logs"));
-    }
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("No Pump TradeEvent found in logs or inner instructions"))
+}
 
-    // Validate the extracted mint address
-    if let Err(_) = Pubkey::from_str(&mint_address) {
-        return Err(anyhow!("Invalid mint address format"));
+// Static keys followed by the ones loaded from lookup tables, writable first:
+// the order instruction indexes refer to.
+fn account_keys(transaction: &EncodedTransactionWithStatusMeta) -> Vec<String> {
+    let mut account_keys = match &transaction.transaction {
+        EncodedTransaction::Json(tx_data) => match &tx_data.message {
+            UiMessage::Raw(message) => message.account_keys.clone(),
+            UiMessage::Parsed(message) => message.account_keys.iter().map(|key| key.pubkey.clone()).collect(),
+        },
+        _ => Vec::new(),
+    };
+    if let Some(OptionSerializer::Some(loaded)) = transaction.meta.as_ref().map(|meta| &meta.loaded_addresses) {
+        account_keys.extend(loaded.writable.iter().cloned());
+        account_keys.extend(loaded.readonly.iter().cloned());
     }
+    account_keys
+}
 
-    println!("Final extracted info - Mint: {}, Is Buy: {}", mint_address, is_buy);
-    Ok((mint_address, is_buy))
+// Mint and direction of the target's Pump trade. Both come from its
+// TradeEvent; the event's discriminator is not an instruction discriminator,
+// and the instruction log lines can't tell which mint was traded.
+pub fn extract_transaction_info(transaction: &EncodedTransactionWithStatusMeta) -> Result<(String, bool)> {
+    let trade = parse_trade_info(transaction)?;
+    Ok((trade.mint.to_string(), trade.is_buy))
 }
 
-async fn execute_swap(pump: &Pump, mint: &str, is_buy: bool, pump_info: &PumpInfo) -> Result<String> {
+#[allow(clippy::too_many_arguments)]
+async fn execute_swap(
+    pump: &Pump,
+    context: &CopyContext,
+    is_buy: bool,
+    pump_info: &PumpInfo,
+    target_trade: Option<&TradeEvent>,
+    target_slot: u64,
+    slippage: u64,
+    use_jito: bool,
+    tip_config: &TipConfig,
+) -> Result<SwapResult> {
     let logger = Logger::new("[EXECUTE SWAP]".to_string());
+    let mint = pump_info.mint.as_str();
     
     // Calculate copy amount (50% of virtual reserves)
     let amount = if is_buy {
         // For buys: 50% of virtual SOL reserves
-        let copy_amount = (pump_info.virtual_sol_reserves as f64 * 0.5) as u64;
+        let copy_amount = copy_buy_amount(pump_info);
         logger.info(format!(
             "Attempting buy with 50% - Amount: {} SOL (from {} total virtual reserves)",
             copy_amount as f64 / 1_000_000_000.0,
             pump_info.virtual_sol_reserves as f64 / 1_000_000_000.0
         ));
 
+        // Don't chase a curve other copiers already pushed past the target's price
+        let copy_amount = match (&context.front_run, target_trade) {
+            (Some(guard), Some(trade)) => match guard.check(pump_info, trade, copy_amount) {
+                FrontRunDecision::Proceed => copy_amount,
+                FrontRunDecision::Downsize(amount) => {
+                    logger.warning(format!(
+                        "[FRONT-RUN] => {} - Quote more than {}% over target fill, downsizing to {} SOL",
+                        mint,
+                        guard.max_premium,
+                        amount as f64 / 1_000_000_000.0
+                    ));
+                    amount
+                }
+                FrontRunDecision::Skip => {
+                    logger.warning(format!(
+                        "[SKIP] => {} - Quote more than {}% over target fill",
+                        mint, guard.max_premium
+                    ));
+                    return Err(Skip::new(
+                        "front_run",
+                        format!("quote more than {}% over target fill", guard.max_premium),
+                    )
+                    .into());
+                }
+            },
+            _ => copy_amount,
+        };
+
         // Check wallet SOL balance
         if let Ok(wallet_balance) = pump.client.get_balance(&pump.keypair.pubkey()).await {
             logger.info(format!(
@@ -389,14 +558,14 @@ async fn execute_swap(pump: &Pump, mint: &str, is_buy: bool, pump_info: &PumpInf
         copy_amount
     } else {
         // For sells: First check current token balance
-        let token_balance = pump.get_token_balance(mint).await?;
+        let token_balance = paper::get_token_balance(context.paper.as_ref(), pump, mint).await?;
         logger.info(format!(
             "Current token balance before sell: {} tokens",
             token_balance
         ));
 
         // For sells: 50% of token balance if we have any
-        let copy_amount = (token_balance as f64 * 0.5) as u64;
+        let copy_amount = copy_sell_amount(token_balance);
         logger.info(format!(
             "Attempting sell with 50% - Amount: {} tokens (from {} total balance)",
             copy_amount,
@@ -414,29 +583,96 @@ async fn execute_swap(pump: &Pump, mint: &str, is_buy: bool, pump_info: &PumpInf
     };
 
     // Execute the swap
-    let signature = if is_buy {
+    let swap_direction = if is_buy {
         logger.info(format!("Executing buy for {} SOL", amount as f64 / 1_000_000_000.0));
-        pump.buy(mint, amount).await
+        SwapDirection::Buy
     } else {
         if amount == 0 {
             logger.error("Cannot execute sell - No tokens available in wallet".to_string());
-            return Err(anyhow!("No tokens available to sell"));
+            return Err(Skip::new("no_tokens", "No tokens available to sell").into());
         }
         logger.info(format!("Executing sell for {} tokens", amount));
-        pump.sell(mint, amount).await
+        SwapDirection::Sell
     };
 
-    match &signature {
-        Ok(sig) => logger.success(format!("Swap executed successfully: {}", sig)),
-        Err(e) => logger.error(format!("Swap failed: {}", e)),
+    let executor = Executor::new(pump.client.clone(), tip_config.clone())
+        .with_journal(context.journal.clone())
+        .with_paper(context.paper.clone());
+    let mut pump_info = pump_info.clone();
+    let mut slippage = slippage;
+
+    let result = loop {
+        let swap_config = SwapConfig {
+            slippage,
+            use_jito,
+            amount,
+            swap_direction: swap_direction.clone(),
+        };
+        let result = executor.swap(pump, &pump_info, swap_config, Some(target_slot)).await;
+
+        let (Err(e), Some(policy)) = (&result, &context.retry_policy) else {
+            break result;
+        };
+        if !policy.is_retryable(is_buy, e) {
+            break result;
+        }
+        let Some(next_slippage) = policy.next_slippage(slippage) else {
+            logger.warning(format!("[NO RETRY] => {} - Slippage cap of {}% reached", mint, policy.max_slippage));
+            break result;
+        };
+
+        // Re-quote from fresh reserves and give up once we'd pay too much over the target
+        pump_info = match pump.refresh_pump_info(&pump_info).await {
+            Ok(pump_info) => pump_info,
+            Err(refresh_error) => {
+                logger.error(format!("[NO RETRY] => {} - Failed to refresh reserves: {}", mint, refresh_error));
+                break result;
+            }
+        };
+        let quote_price = get_buy_price(&pump_info, amount);
+        let target_price = target_trade.and_then(|trade| trade.price());
+        match (quote_price, target_price) {
+            (Some(quote), Some(target)) if policy.within_premium(quote, target) => {}
+            _ => {
+                logger.warning(format!(
+                    "[NO RETRY] => {} - Quote {:?} vs target fill {:?} exceeds the {}% premium",
+                    mint, quote_price, target_price, policy.max_price_premium
+                ));
+                break result;
+            }
+        }
+
+        logger.info(format!(
+            "[RETRY] => {} - Slippage exceeded at {}%, retrying at {}%",
+            mint, slippage, next_slippage
+        ));
+        slippage = next_slippage;
+    };
+
+    match &result {
+        Ok(swap_result) if swap_result.landed() => logger.success(format!(
+            "Swap executed successfully: {} via {} at slot {} (+{} slots behind target)",
+            swap_result.signature,
+            swap_result.landed_path.as_ref().map(|path| path.to_string()).unwrap_or_default(),
+            swap_result.landed_slot.unwrap_or_default(),
+            swap_result.slot_delta.unwrap_or_default()
+        )),
+        Ok(swap_result) => logger.error(format!(
+            "Swap not landed: {} (bundle {:?})",
+            swap_result.signature,
+            swap_result.bundle_outcome()
+        )),
+        Err(e) => match (pump_error(e), e.downcast_ref::<SimulationFailure>()) {
+            (Some(PumpError::BondingCurveComplete), _) => {
+                logger.warning(format!("[SKIP] => {} - Bonding curve complete, token has migrated", mint))
+            }
+            (Some(error), _) if error.is_slippage() => {
+                logger.warning(format!("[SKIP] => {} - Slippage exceeded: {}", mint, e))
+            }
+            (_, Some(failure)) => logger.warning(format!("[SKIP] => {} - {}", mint, failure)),
+            _ => logger.error(format!("Swap failed: {}", e)),
+        },
     }
 
-    signature
+    result
 }
-
-#[derive(Debug)]
-struct TradeInfo {
-    mint: String,
-    amount: u64,
-    is_buy: bool,
-} 
\ No newline at end of file
@@ -0,0 +1,214 @@
+use {
+    crate::{
+        common::{logger::Logger, utils::{import_optional_env_var, SwapConfig, SwapDirection}},
+        dex::{
+            pump_error::PumpError,
+            pump_fun::{get_curve_sol_amount, simulate_swap, Pump, PumpInfo},
+        },
+        engine::{
+            executor::{SendPath, SwapResult},
+            journal::read_journal,
+            positions::{sol_amounts, Fill},
+        },
+        services::jito::{self, TipConfig},
+    },
+    anyhow::{anyhow, Result},
+    chrono::Utc,
+    solana_sdk::commitment_config::CommitmentConfig,
+    std::{collections::HashMap, path::Path, sync::{Arc, Mutex}},
+};
+
+const DEFAULT_PAPER_BALANCE: f64 = 10.0; // SOL
+const DEFAULT_PAPER_TX_FEE: u64 = 5_000; // lamports
+// Rent for the token account a first buy of a mint creates
+pub const TOKEN_ACCOUNT_RENT: u64 = 2_039_280; // lamports
+
+// Balances of the simulated wallet
+#[derive(Debug, Clone, Default)]
+pub struct PaperHoldings {
+    pub starting_balance: u64,
+    pub cash: u64,
+    pub tokens: HashMap<String, u64>,
+    pub fee: u64,
+    pub fills: u64,
+}
+
+impl PaperHoldings {
+    fn apply(&mut self, fill: &Fill) {
+        let tokens = self.tokens.entry(fill.mint.clone()).or_default();
+        if fill.is_buy {
+            *tokens += fill.token_amount;
+            self.cash = self.cash.saturating_sub(fill.sol_amount);
+        } else {
+            *tokens = tokens.saturating_sub(fill.token_amount);
+            self.cash = (self.cash + fill.sol_amount).saturating_sub(fill.excess_cost);
+        }
+        self.fills += 1;
+    }
+}
+
+pub fn paper_mode() -> bool {
+    import_optional_env_var("PAPER_TRADING").is_some_and(|v| matches!(v.trim(), "true" | "1"))
+}
+
+// Starting balance in lamports, from `PAPER_BALANCE` in SOL.
+pub fn paper_balance() -> Result<u64> {
+    let balance = import_optional_env_var("PAPER_BALANCE")
+        .map(|v| v.parse::<f64>().map_err(|e| anyhow!("Invalid PAPER_BALANCE: {}", e)))
+        .transpose()?
+        .unwrap_or(DEFAULT_PAPER_BALANCE);
+    Ok((balance * 1_000_000_000.0) as u64)
+}
+
+// Network fee charged per simulated swap, from `PAPER_TX_FEE` in lamports.
+pub fn paper_fee() -> Result<u64> {
+    Ok(import_optional_env_var("PAPER_TX_FEE")
+        .map(|v| v.parse::<u64>().map_err(|e| anyhow!("Invalid PAPER_TX_FEE: {}", e)))
+        .transpose()?
+        .unwrap_or(DEFAULT_PAPER_TX_FEE))
+}
+
+// The simulated wallet while `PAPER_TRADING` is on, shared by copies, exits
+// and reconciliation.
+#[derive(Debug, Clone)]
+pub struct PaperPortfolio {
+    holdings: Arc<Mutex<PaperHoldings>>,
+}
+
+impl PaperPortfolio {
+    // `None` unless `PAPER_TRADING` is set. The portfolio starts with
+    // `PAPER_BALANCE` SOL and replays the paper fills already in the journal,
+    // so it carries over restarts.
+    pub fn from_env(journal_path: &Path) -> Result<Option<Self>> {
+        if !paper_mode() {
+            return Ok(None);
+        }
+        let starting_balance = paper_balance()?;
+        let fee = paper_fee()?;
+        let mut holdings = PaperHoldings {
+            starting_balance,
+            cash: starting_balance,
+            fee,
+            ..PaperHoldings::default()
+        };
+        let paper_path = SendPath::Paper.to_string();
+        for record in read_journal(journal_path).unwrap_or_default() {
+            if let Some(fill) = record.fill.filter(|_| record.send_path.as_deref() == Some(paper_path.as_str())) {
+                holdings.apply(&fill);
+            }
+        }
+
+        Ok(Some(Self {
+            holdings: Arc::new(Mutex::new(holdings)),
+        }))
+    }
+
+    pub fn holdings(&self) -> Result<PaperHoldings> {
+        Ok(self.holdings.lock().map_err(|e| anyhow!("{}", e))?.clone())
+    }
+
+    // Fills a swap built from `pump_info` against the curve's current reserves,
+    // enforcing the same slippage bounds the Pump program would. Nothing is sent.
+    pub async fn swap(
+        &self,
+        pump: &Pump,
+        pump_info: &PumpInfo,
+        config: &SwapConfig,
+        target_slot: Option<u64>,
+        tip_config: &TipConfig,
+    ) -> Result<SwapResult> {
+        let logger = Logger::new("[PAPER]".to_string());
+        if pump_info.complete {
+            return Err(PumpError::BondingCurveComplete.into());
+        }
+
+        let current = pump.refresh_pump_info(pump_info).await?;
+        let tip = if config.use_jito {
+            (jito::get_tip_value(tip_config)? * 1_000_000_000.0) as u64
+        } else {
+            0
+        };
+        let slot = pump
+            .client
+            .get_slot_with_commitment(CommitmentConfig::processed())
+            .await?;
+
+        let mut portfolio = self.holdings.lock().map_err(|e| anyhow!("{}", e))?;
+        let held = portfolio.tokens.get(&pump_info.mint).copied().unwrap_or_default();
+
+        let (token_amount, sol_change) = match config.swap_direction {
+            SwapDirection::Buy => {
+                let (token_amount, cost) = simulate_swap(pump_info, &current, config)?;
+                let rent = if held == 0 { TOKEN_ACCOUNT_RENT } else { 0 };
+                let total = cost + portfolio.fee + tip + rent;
+                if total > portfolio.cash {
+                    return Err(anyhow!(
+                        "Paper balance of {} SOL can't cover {} SOL",
+                        portfolio.cash as f64 / 1_000_000_000.0,
+                        total as f64 / 1_000_000_000.0
+                    ));
+                }
+                (token_amount, -(total as i64))
+            }
+            SwapDirection::Sell => {
+                if config.amount > held {
+                    return Err(anyhow!("Paper holding of {} tokens can't cover {}", held, config.amount));
+                }
+                let (token_amount, proceeds) = simulate_swap(pump_info, &current, config)?;
+                (token_amount, proceeds as i64 - (portfolio.fee + tip) as i64)
+            }
+        };
+
+        let is_buy = matches!(config.swap_direction, SwapDirection::Buy);
+        let (sol_amount, excess_cost) = sol_amounts(is_buy, sol_change);
+        let fill = Fill {
+            signature: format!("paper-{}-{}", Utc::now().timestamp_millis(), portfolio.fills),
+            mint: pump_info.mint.clone(),
+            is_buy,
+            slot,
+            token_amount,
+            sol_amount,
+            excess_cost,
+            fee: portfolio.fee,
+            curve_sol_amount: Some(get_curve_sol_amount(&current, is_buy, token_amount)),
+        };
+        portfolio.apply(&fill);
+        logger.success(format!(
+            "[PAPER-FILL] => {} {} - {} tokens for {} SOL, cash {} SOL",
+            if fill.is_buy { "BUY" } else { "SELL" },
+            fill.mint,
+            fill.token_amount,
+            fill.sol_amount as f64 / 1_000_000_000.0,
+            portfolio.cash as f64 / 1_000_000_000.0
+        ));
+
+        Ok(SwapResult {
+            signature: fill.signature.clone(),
+            landed_path: Some(SendPath::Paper),
+            landed_slot: Some(slot),
+            slot_delta: target_slot.map(|target_slot| slot as i64 - target_slot as i64),
+            paths: Vec::new(),
+            fill: Some(fill),
+        })
+    }
+
+    pub fn log_summary(&self) {
+        let Ok(portfolio) = self.holdings() else { return };
+        let logger = Logger::new("[PAPER]".to_string());
+        logger.info(format!(
+            "[PORTFOLIO] => Cash: {} SOL (started {} SOL), Holdings: {}, Fills: {}",
+            portfolio.cash as f64 / 1_000_000_000.0,
+            portfolio.starting_balance as f64 / 1_000_000_000.0,
+            portfolio.tokens.values().filter(|tokens| **tokens > 0).count(),
+            portfolio.fills
+        ));
+    }
+}
+
+// The paper holding of `mint` in paper mode, the wallet's balance otherwise.
+pub async fn get_token_balance(paper: Option<&PaperPortfolio>, pump: &Pump, mint: &str) -> Result<u64> {
+    match paper {
+        Some(paper) => Ok(paper.holdings()?.tokens.get(mint).copied().unwrap_or_default()),
+        None => pump.get_token_balance(mint).await,
+    }
+}
@@ -0,0 +1,423 @@
+use {
+    crate::{
+        common::logger::Logger,
+        dex::pump_fun::{
+            decode_trade_event, get_pda, get_sell_sol_amount, pump_info_from_account, BondingCurveAccount, PumpInfo,
+            PUMP_PROGRAM,
+        },
+        engine::executor::SwapResult,
+    },
+    anyhow::{anyhow, Result},
+    chrono::Utc,
+    serde::{Deserialize, Serialize},
+    solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig},
+    solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature},
+    solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding},
+    std::{
+        collections::HashMap,
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
+};
+
+const FILL_RETRIES: u32 = 3;
+const FILL_RETRY_DELAY: u64 = 500; // milliseconds
+const MARK_INTERVAL: u64 = 5; // seconds
+
+// What one of our landed swaps actually did to the wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub signature: String,
+    pub mint: String,
+    pub is_buy: bool,
+    pub slot: u64,
+    pub token_amount: u64,
+    // Lamports that left the wallet on a buy or reached it on a sell, after the
+    // network fee, priority fee, tip and any token account rent
+    pub sol_amount: u64,
+    // Lamports a sell cost beyond its proceeds, when the fees and tip
+    // outweighed them; `sol_amount` is 0 then
+    #[serde(default)]
+    pub excess_cost: u64,
+    pub fee: u64,
+    // Lamports that moved through the bonding curve, from our own TradeEvent,
+    // so without the Pump fee or any other cost
+    #[serde(default)]
+    pub curve_sol_amount: Option<u64>,
+}
+
+// Splits a swap's net lamport change into a fill's `sol_amount` and
+// `excess_cost`.
+pub fn sol_amounts(is_buy: bool, sol_change: i64) -> (u64, u64) {
+    match (is_buy, sol_change) {
+        (true, change) => ((-change).max(0) as u64, 0),
+        (false, change) if change >= 0 => (change as u64, 0),
+        (false, change) => (0, change.unsigned_abs()),
+    }
+}
+
+impl Fill {
+    // Lamports per raw token unit on the curve, like a TradeEvent's price
+    pub fn curve_price(&self) -> Option<f64> {
+        if self.token_amount == 0 {
+            return None;
+        }
+        Some(self.curve_sol_amount? as f64 / self.token_amount as f64)
+    }
+
+    // Lamports paid beyond the curve price: the Pump fee, network and
+    // priority fees, tip and rent. Only the network fee is known for fills
+    // without a curve amount.
+    pub fn costs(&self) -> u64 {
+        match self.curve_sol_amount {
+            Some(curve_sol_amount) if self.is_buy => self.sol_amount.saturating_sub(curve_sol_amount),
+            Some(curve_sol_amount) => (curve_sol_amount + self.excess_cost).saturating_sub(self.sol_amount),
+            None => self.fee,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Position {
+    pub mint: String,
+    pub token_amount: u64,
+    // Lamports paid for the tokens still held
+    pub cost_basis: u64,
+    pub total_spent: u64,
+    pub total_received: u64,
+    pub realized_pnl: i64,
+    pub fees: u64,
+    pub buys: u32,
+    pub sells: u32,
+    pub opened_at: i64,
+    pub updated_at: i64,
+    // Marked from the latest bonding-curve reserves
+    pub unrealized_pnl: Option<i64>,
+    // Found on-chain at startup with no journal history, so the cost basis
+    // is unknown
+    pub untracked: bool,
+}
+
+impl Position {
+    // What selling everything held would return now, less its cost basis.
+    pub fn unrealized_pnl_at(&self, pump_info: &PumpInfo) -> i64 {
+        get_sell_sol_amount(pump_info, self.token_amount) as i64 - self.cost_basis as i64
+    }
+
+    pub fn apply(&mut self, fill: &Fill) {
+        let now = Utc::now().timestamp();
+        if self.buys == 0 && self.sells == 0 {
+            self.opened_at = now;
+        }
+        self.updated_at = now;
+        self.fees += fill.fee;
+
+        if fill.is_buy {
+            self.buys += 1;
+            self.token_amount += fill.token_amount;
+            self.cost_basis += fill.sol_amount;
+            self.total_spent += fill.sol_amount;
+        } else {
+            self.sells += 1;
+            // Release cost basis in proportion to the tokens sold
+            let sold = fill.token_amount.min(self.token_amount);
+            let released = if self.token_amount == 0 {
+                0
+            } else {
+                (self.cost_basis as u128 * sold as u128 / self.token_amount as u128) as u64
+            };
+            self.token_amount -= sold;
+            self.cost_basis -= released;
+            self.total_received += fill.sol_amount;
+            self.total_spent += fill.excess_cost;
+            self.realized_pnl += fill.sol_amount as i64 - fill.excess_cost as i64 - released as i64;
+        }
+        if self.token_amount == 0 {
+            self.unrealized_pnl = None;
+        }
+    }
+}
+
+// The bot's holdings and PnL per mint, updated from confirmed fills.
+#[derive(Clone, Default)]
+pub struct PositionBook {
+    positions: Arc<Mutex<HashMap<String, Position>>>,
+}
+
+impl PositionBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply(&self, fill: &Fill) -> Option<Position> {
+        let mut positions = self.positions.lock().ok()?;
+        let position = positions.entry(fill.mint.clone()).or_insert_with(|| Position {
+            mint: fill.mint.clone(),
+            ..Position::default()
+        });
+        position.apply(fill);
+        Some(position.clone())
+    }
+
+    // Fetches the fill of a landed swap and books it. Paper fills come with
+    // the result.
+    pub async fn record_fill(
+        &self,
+        client: &RpcClient,
+        wallet: &Pubkey,
+        mint: &str,
+        swap_result: &SwapResult,
+    ) -> Result<(Fill, Position)> {
+        let logger = Logger::new("[POSITIONS]".to_string());
+        let fill = match &swap_result.fill {
+            Some(fill) => fill.clone(),
+            None => get_fill(client, wallet, mint, &swap_result.signature).await?,
+        };
+        let position = self
+            .apply(&fill)
+            .ok_or_else(|| anyhow!("Position book unavailable"))?;
+
+        logger.info(format!(
+            "[FILL] => {} {} - {} tokens for {} SOL (fee {}), holding {} tokens, realized {} SOL",
+            if fill.is_buy { "BUY" } else { "SELL" },
+            mint,
+            fill.token_amount,
+            fill.sol_amount as f64 / 1_000_000_000.0,
+            fill.fee,
+            position.token_amount,
+            position.realized_pnl as f64 / 1_000_000_000.0
+        ));
+        Ok((fill, position))
+    }
+
+    // Replaces the book's position for its mint, e.g. after reconciliation.
+    pub fn insert(&self, position: Position) {
+        if let Ok(mut positions) = self.positions.lock() {
+            positions.insert(position.mint.clone(), position);
+        }
+    }
+
+    pub fn mark(&self, mint: &str, pump_info: &PumpInfo) {
+        if let Ok(mut positions) = self.positions.lock() {
+            if let Some(position) = positions.get_mut(mint).filter(|position| position.token_amount > 0) {
+                position.unrealized_pnl = Some(position.unrealized_pnl_at(pump_info));
+            }
+        }
+    }
+
+    // Marks every open position from its bonding curve, whether or not the
+    // exit manager is watching it.
+    pub async fn run_marks(self, client: Arc<RpcClient>) {
+        let logger = Logger::new("[POSITIONS]".to_string());
+        let mut interval = tokio::time::interval(Duration::from_secs(MARK_INTERVAL));
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.mark_open(&client).await {
+                logger.warning(format!("Failed to mark positions: {}", e));
+            }
+        }
+    }
+
+    async fn mark_open(&self, client: &RpcClient) -> Result<()> {
+        let program_id = Pubkey::from_str(PUMP_PROGRAM)?;
+        let mints: Vec<Pubkey> = self
+            .positions()
+            .iter()
+            .filter(|position| position.token_amount > 0)
+            .map(|position| Pubkey::from_str(&position.mint))
+            .collect::<Result<_, _>>()?;
+        if mints.is_empty() {
+            return Ok(());
+        }
+
+        let bonding_curves: Vec<Pubkey> = mints
+            .iter()
+            .map(|mint| get_pda(mint, &program_id))
+            .collect::<Result<_>>()?;
+        let accounts = client.get_multiple_accounts(&bonding_curves).await?;
+
+        for ((mint, bonding_curve), account) in mints.iter().zip(&bonding_curves).zip(accounts) {
+            let Some(account) = account else { continue };
+            // A migrated curve no longer prices the token
+            let Ok(curve) = BondingCurveAccount::decode(&account.data) else { continue };
+            if curve.complete || curve.virtual_token_reserves == 0 {
+                continue;
+            }
+            self.mark(&mint.to_string(), &pump_info_from_account(mint, bonding_curve, &curve));
+        }
+        Ok(())
+    }
+
+    pub fn positions(&self) -> Vec<Position> {
+        self.positions
+            .lock()
+            .map(|positions| positions.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn log_summary(&self) {
+        let logger = Logger::new("[POSITIONS]".to_string());
+        let positions = self.positions();
+        if positions.is_empty() {
+            return;
+        }
+
+        let open = positions.iter().filter(|position| position.token_amount > 0).count();
+        let realized: i64 = positions.iter().map(|position| position.realized_pnl).sum();
+        let unrealized: i64 = positions.iter().filter_map(|position| position.unrealized_pnl).sum();
+        logger.info(format!(
+            "[PNL] => Open: {}, Realized: {} SOL, Unrealized: {} SOL",
+            open,
+            realized as f64 / 1_000_000_000.0,
+            unrealized as f64 / 1_000_000_000.0
+        ));
+    }
+}
+
+// Reads our fill from the landed transaction's balance changes. The wallet is
+// the fee payer, so its lamport change covers every cost of the swap.
+pub async fn get_fill(client: &RpcClient, wallet: &Pubkey, mint: &str, signature: &str) -> Result<Fill> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let parsed_signature = Signature::from_str(signature)?;
+
+    let mut last_error = None;
+    for _ in 0..FILL_RETRIES {
+        match client.get_transaction_with_config(&parsed_signature, config).await {
+            Ok(transaction) => {
+                let meta = transaction
+                    .transaction
+                    .meta
+                    .ok_or_else(|| anyhow!("Transaction {} has no meta", signature))?;
+                let sol_change = *meta.post_balances.first().unwrap_or(&0) as i64
+                    - *meta.pre_balances.first().unwrap_or(&0) as i64;
+                let token_change = token_balance(&meta.post_token_balances, wallet, mint) as i128
+                    - token_balance(&meta.pre_token_balances, wallet, mint) as i128;
+                let is_buy = token_change > 0;
+                let (sol_amount, excess_cost) = sol_amounts(is_buy, sol_change);
+                let curve_sol_amount = match &meta.log_messages {
+                    OptionSerializer::Some(logs) => logs
+                        .iter()
+                        .filter_map(|log| log.strip_prefix("Program data: "))
+                        .filter_map(decode_trade_event)
+                        .find(|trade| trade.user == *wallet && trade.mint.to_string() == mint)
+                        .map(|trade| trade.sol_amount),
+                    _ => None,
+                };
+
+                return Ok(Fill {
+                    signature: signature.to_string(),
+                    mint: mint.to_string(),
+                    is_buy,
+                    slot: transaction.slot,
+                    token_amount: token_change.unsigned_abs() as u64,
+                    sol_amount,
+                    excess_cost,
+                    fee: meta.fee,
+                    curve_sol_amount,
+                });
+            }
+            Err(e) => last_error = Some(anyhow!("Failed to get transaction {}: {}", signature, e)),
+        }
+        tokio::time::sleep(Duration::from_millis(FILL_RETRY_DELAY)).await;
+    }
+
+    Err(last_error.unwrap_or_else(|| anyhow!("Failed to get transaction {}", signature)))
+}
+
+fn token_balance(
+    balances: &OptionSerializer<Vec<solana_transaction_status::UiTransactionTokenBalance>>,
+    owner: &Pubkey,
+    mint: &str,
+) -> u64 {
+    let owner = owner.to_string();
+    match balances {
+        OptionSerializer::Some(balances) => balances
+            .iter()
+            .filter(|balance| balance.mint == mint)
+            .filter(|balance| matches!(&balance.owner, OptionSerializer::Some(o) if *o == owner))
+            .filter_map(|balance| balance.ui_token_amount.amount.parse::<u64>().ok())
+            .sum(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(is_buy: bool, token_amount: u64, sol_amount: u64) -> Fill {
+        Fill {
+            signature: String::new(),
+            mint: "mint".to_string(),
+            is_buy,
+            slot: 0,
+            token_amount,
+            sol_amount,
+            excess_cost: 0,
+            fee: 5_000,
+            curve_sol_amount: None,
+        }
+    }
+
+    #[test]
+    fn partial_sells_release_cost_basis_in_proportion() {
+        let book = PositionBook::new();
+        book.apply(&fill(true, 1_000, 100_000));
+        let position = book.apply(&fill(true, 1_000, 300_000)).unwrap();
+        assert_eq!(position.token_amount, 2_000);
+        assert_eq!(position.cost_basis, 400_000);
+
+        // A quarter of the holding carries a quarter of the cost
+        let position = book.apply(&fill(false, 500, 150_000)).unwrap();
+        assert_eq!(position.token_amount, 1_500);
+        assert_eq!(position.cost_basis, 300_000);
+        assert_eq!(position.realized_pnl, 50_000);
+
+        let position = book.apply(&fill(false, 1_500, 240_000)).unwrap();
+        assert_eq!(position.token_amount, 0);
+        assert_eq!(position.cost_basis, 0);
+        assert_eq!(position.realized_pnl, -10_000);
+        assert_eq!((position.total_spent, position.total_received), (400_000, 390_000));
+        assert_eq!((position.buys, position.sells, position.fees), (2, 2, 20_000));
+    }
+
+    #[test]
+    fn overselling_only_releases_what_is_held() {
+        let mut position = Position::default();
+        position.apply(&fill(true, 1_000, 100_000));
+        position.unrealized_pnl = Some(1);
+
+        position.apply(&fill(false, 3_000, 250_000));
+        assert_eq!(position.token_amount, 0);
+        assert_eq!(position.cost_basis, 0);
+        assert_eq!(position.realized_pnl, 150_000);
+        assert_eq!(position.unrealized_pnl, None);
+
+        // Selling with nothing held books the proceeds as profit
+        position.apply(&fill(false, 100, 1_000));
+        assert_eq!(position.realized_pnl, 151_000);
+    }
+
+    #[test]
+    fn a_sell_costing_more_than_it_returns_books_a_loss() {
+        assert_eq!(sol_amounts(true, -100_000), (100_000, 0));
+        assert_eq!(sol_amounts(false, 40_000), (40_000, 0));
+        assert_eq!(sol_amounts(false, -15_000), (0, 15_000));
+
+        let mut position = Position::default();
+        position.apply(&fill(true, 1_000, 100_000));
+        let (sol_amount, excess_cost) = sol_amounts(false, -15_000);
+        position.apply(&Fill {
+            excess_cost,
+            ..fill(false, 1_000, sol_amount)
+        });
+        assert_eq!(position.realized_pnl, -115_000);
+        assert_eq!((position.total_spent, position.total_received), (115_000, 0));
+    }
+}
@@ -0,0 +1,287 @@
+use {
+    crate::{
+        common::logger::Logger,
+        dex::pump_fun::{get_pda, pump_info_from_account, BondingCurveAccount, PUMP_PROGRAM},
+        engine::{
+            exit::ExitManager,
+            journal::read_journal,
+            paper::PaperPortfolio,
+            positions::{Position, PositionBook},
+            report::PnlReport,
+        },
+    },
+    anyhow::Result,
+    chrono::Utc,
+    solana_account_decoder::UiAccountData,
+    solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::TokenAccountsFilter, rpc_response::RpcKeyedAccount},
+    solana_sdk::pubkey::Pubkey,
+    std::{collections::BTreeMap, path::Path, str::FromStr},
+};
+
+// get_multiple_accounts takes at most this many keys per call
+const MULTIPLE_ACCOUNTS_CHUNK: usize = 100;
+
+// Rebuilds the position book from the wallet's token accounts on startup, so
+// holdings survive crashes and manual trades. Journal positions keep their cost
+// basis with the balance corrected to what's on-chain; Pump holdings the journal
+// doesn't know about come in as untracked. Every held mint still on its curve
+// is handed to the exit manager. In paper mode the paper holdings stand in
+// for the token accounts.
+pub async fn reconcile_positions(
+    client: &RpcClient,
+    wallet: &Pubkey,
+    journal_path: &Path,
+    book: &PositionBook,
+    exit_manager: &ExitManager,
+    paper: Option<&PaperPortfolio>,
+) -> Result<()> {
+    let logger = Logger::new("[RECONCILE]".to_string());
+
+    let mut journal_positions = match read_journal(journal_path) {
+        Ok(records) => PnlReport::from_records(&records).positions,
+        Err(e) => {
+            logger.warning(format!("No journal history: {}", e));
+            BTreeMap::new()
+        }
+    };
+    journal_positions.retain(|_, position| position.token_amount > 0);
+
+    let balances = match paper {
+        Some(paper) => paper.holdings()?.tokens.into_iter().filter(|(_, amount)| *amount > 0).collect(),
+        None => get_token_balances(client, wallet).await?,
+    };
+    let program_id = Pubkey::from_str(PUMP_PROGRAM)?;
+    let mints: Vec<Pubkey> = balances
+        .keys()
+        .filter_map(|mint| Pubkey::from_str(mint).ok())
+        .collect();
+
+    // Only mints with a Pump bonding curve are ours to manage
+    let mut curves = BTreeMap::new();
+    for chunk in mints.chunks(MULTIPLE_ACCOUNTS_CHUNK) {
+        let bonding_curves = chunk
+            .iter()
+            .map(|mint| get_pda(mint, &program_id))
+            .collect::<Result<Vec<Pubkey>>>()?;
+        let accounts = client.get_multiple_accounts(&bonding_curves).await?;
+
+        for ((mint, bonding_curve), account) in chunk.iter().zip(bonding_curves).zip(accounts) {
+            let Some(account) = account.filter(|account| account.owner == program_id) else { continue };
+            let Ok(curve) = BondingCurveAccount::decode(&account.data) else { continue };
+            curves.insert(mint.to_string(), (*mint, bonding_curve, curve));
+        }
+    }
+    let pump_balances = balances
+        .iter()
+        .filter(|(mint, _)| curves.contains_key(*mint))
+        .map(|(mint, amount)| (mint.clone(), *amount))
+        .collect();
+
+    let reconciliation = reconcile(journal_positions, &pump_balances, Utc::now().timestamp());
+    for (mint, journal_amount, balance) in &reconciliation.mismatched {
+        logger.warning(format!(
+            "[MISMATCH] => {} - journal {} tokens, on-chain {}",
+            mint, journal_amount, balance
+        ));
+    }
+    for position in reconciliation.positions.iter().filter(|position| position.untracked) {
+        logger.warning(format!(
+            "[UNTRACKED] => {} - {} tokens with no journal history",
+            position.mint, position.token_amount
+        ));
+    }
+    // Journal positions with nothing left on-chain were closed outside the bot
+    for mint in &reconciliation.closed {
+        logger.warning(format!("[CLOSED] => {} - in the journal but no longer held", mint));
+    }
+
+    let held = reconciliation.positions.len();
+    for position in reconciliation.positions {
+        let (mint, bonding_curve, curve) = &curves[&position.mint];
+        if curve.complete {
+            logger.warning(format!("[MIGRATED] => {} - bonding curve complete, not watched for exits", mint));
+        } else {
+            // The curve price our buys landed at isn't journaled, so
+            // exits measure from the first price seen
+            exit_manager.register(&pump_info_from_account(mint, bonding_curve, curve), None);
+        }
+        book.insert(position);
+    }
+
+    logger.success(format!("Reconciled {} Pump positions from {} token holdings", held, balances.len()));
+    Ok(())
+}
+
+// Open journal positions set against the Pump tokens the wallet holds
+#[derive(Debug, Default)]
+struct Reconciliation {
+    positions: Vec<Position>,
+    // Mint, journal amount and on-chain amount
+    mismatched: Vec<(String, u64, u64)>,
+    closed: Vec<String>,
+}
+
+// Journal positions keep their cost basis with the balance corrected to
+// what's held; holdings without one come in as untracked.
+fn reconcile(
+    mut journal_positions: BTreeMap<String, Position>,
+    balances: &BTreeMap<String, u64>,
+    now: i64,
+) -> Reconciliation {
+    let mut reconciliation = Reconciliation::default();
+    for (mint, &balance) in balances {
+        let position = match journal_positions.remove(mint) {
+            Some(mut position) => {
+                if position.token_amount != balance {
+                    reconciliation.mismatched.push((mint.clone(), position.token_amount, balance));
+                    // Keep the average cost for whatever is actually held
+                    position.cost_basis =
+                        (position.cost_basis as u128 * balance as u128 / position.token_amount as u128) as u64;
+                    position.token_amount = balance;
+                }
+                position
+            }
+            None => Position {
+                mint: mint.clone(),
+                token_amount: balance,
+                opened_at: now,
+                updated_at: now,
+                untracked: true,
+                ..Position::default()
+            },
+        };
+        reconciliation.positions.push(position);
+    }
+    reconciliation.closed = journal_positions.into_keys().collect();
+    reconciliation
+}
+
+// Raw token amount per mint across the wallet's SPL Token and Token-2022
+// accounts.
+async fn get_token_balances(client: &RpcClient, wallet: &Pubkey) -> Result<BTreeMap<String, u64>> {
+    let mut accounts = Vec::new();
+    for token_program in [spl_token::id(), spl_token_2022::id()] {
+        accounts.extend(
+            client
+                .get_token_accounts_by_owner(wallet, TokenAccountsFilter::ProgramId(token_program))
+                .await?,
+        );
+    }
+    Ok(sum_token_balances(accounts))
+}
+
+fn sum_token_balances(accounts: Vec<RpcKeyedAccount>) -> BTreeMap<String, u64> {
+    let mut balances = BTreeMap::new();
+    for keyed_account in accounts {
+        let UiAccountData::Json(parsed) = keyed_account.account.data else { continue };
+        let info = &parsed.parsed["info"];
+        let (Some(mint), Some(amount)) = (
+            info["mint"].as_str(),
+            info["tokenAmount"]["amount"].as_str().and_then(|amount| amount.parse::<u64>().ok()),
+        ) else {
+            continue;
+        };
+        if amount > 0 {
+            *balances.entry(mint.to_string()).or_insert(0) += amount;
+        }
+    }
+    balances
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        serde_json::json,
+        solana_account_decoder::{parse_account_data::ParsedAccount, UiAccount},
+    };
+
+    const MINT: &str = "3x4YYzN2Fjc6PzGoH4mb6rHFA96hVWLKD6oYpKJrpump";
+    const OTHER_MINT: &str = "So11111111111111111111111111111111111111112";
+
+    fn journal_position(mint: &str, token_amount: u64, cost_basis: u64) -> Position {
+        Position {
+            mint: mint.to_string(),
+            token_amount,
+            cost_basis,
+            buys: 1,
+            ..Position::default()
+        }
+    }
+
+    fn token_account(program: &str, owner: &str, mint: &str, amount: u64) -> RpcKeyedAccount {
+        RpcKeyedAccount {
+            pubkey: Pubkey::new_unique().to_string(),
+            account: UiAccount {
+                lamports: 2_039_280,
+                data: UiAccountData::Json(ParsedAccount {
+                    program: program.to_string(),
+                    parsed: json!({
+                        "type": "account",
+                        "info": {
+                            "mint": mint,
+                            "owner": Pubkey::new_unique().to_string(),
+                            "tokenAmount": { "amount": amount.to_string(), "decimals": 6 },
+                        },
+                    }),
+                    space: 165,
+                }),
+                owner: owner.to_string(),
+                executable: false,
+                rent_epoch: u64::MAX,
+                space: Some(165),
+            },
+        }
+    }
+
+    #[test]
+    fn a_wallet_only_balance_comes_in_untracked() {
+        let balances = BTreeMap::from([(MINT.to_string(), 1_000)]);
+        let reconciliation = reconcile(BTreeMap::new(), &balances, 1_700_000_000);
+
+        assert_eq!(reconciliation.positions.len(), 1);
+        let position = &reconciliation.positions[0];
+        assert!(position.untracked);
+        assert_eq!(position.token_amount, 1_000);
+        assert_eq!(position.cost_basis, 0);
+        assert_eq!(position.opened_at, 1_700_000_000);
+        assert!(reconciliation.mismatched.is_empty());
+        assert!(reconciliation.closed.is_empty());
+    }
+
+    #[test]
+    fn a_book_only_position_is_reported_closed() {
+        let journal = BTreeMap::from([(MINT.to_string(), journal_position(MINT, 1_000, 500_000))]);
+        let reconciliation = reconcile(journal, &BTreeMap::new(), 1_700_000_000);
+
+        assert!(reconciliation.positions.is_empty());
+        assert_eq!(reconciliation.closed, vec![MINT.to_string()]);
+    }
+
+    #[test]
+    fn a_quantity_mismatch_keeps_the_average_cost() {
+        let journal = BTreeMap::from([(MINT.to_string(), journal_position(MINT, 1_000, 500_000))]);
+        let balances = BTreeMap::from([(MINT.to_string(), 400)]);
+        let reconciliation = reconcile(journal, &balances, 1_700_000_000);
+
+        assert_eq!(reconciliation.mismatched, vec![(MINT.to_string(), 1_000, 400)]);
+        let position = &reconciliation.positions[0];
+        assert!(!position.untracked);
+        assert_eq!(position.token_amount, 400);
+        assert_eq!(position.cost_basis, 200_000);
+        assert_eq!(position.buys, 1);
+    }
+
+    #[test]
+    fn token_2022_accounts_count_alongside_spl_token() {
+        let accounts = vec![
+            token_account("spl-token", &spl_token::id().to_string(), OTHER_MINT, 5),
+            token_account("spl-token-2022", &spl_token_2022::id().to_string(), MINT, 700),
+            token_account("spl-token-2022", &spl_token_2022::id().to_string(), MINT, 300),
+            token_account("spl-token-2022", &spl_token_2022::id().to_string(), OTHER_MINT, 0),
+        ];
+
+        let balances = sum_token_balances(accounts);
+        assert_eq!(balances, BTreeMap::from([(MINT.to_string(), 1_000), (OTHER_MINT.to_string(), 5)]));
+    }
+}
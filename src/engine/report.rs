@@ -0,0 +1,319 @@
+use {
+    crate::engine::{
+        journal::{Decision, JournalRecord},
+        positions::Position,
+    },
+    chrono::{DateTime, Utc},
+    std::collections::{BTreeMap, HashMap},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct PnlStats {
+    pub realized_pnl: i64,
+    // Positions fully sold
+    pub closed: u32,
+    pub wins: u32,
+    pub total_hold_secs: i64,
+    // Percent worse than the target's curve price, summed over fills with a
+    // target trade and a curve price of their own
+    pub total_slippage: f64,
+    pub slippage_fills: u32,
+    // Lamports paid beyond the curve price (Pump fee, network fees, tips,
+    // rent); only network fees for fills without a curve price
+    pub fees: u64,
+    // Swaps sent, and of those the ones that landed
+    pub sent: u32,
+    pub landed: u32,
+}
+
+impl PnlStats {
+    pub fn win_rate(&self) -> Option<f64> {
+        (self.closed > 0).then(|| self.wins as f64 * 100.0 / self.closed as f64)
+    }
+
+    pub fn average_hold_secs(&self) -> Option<f64> {
+        (self.closed > 0).then(|| self.total_hold_secs as f64 / self.closed as f64)
+    }
+
+    pub fn average_slippage(&self) -> Option<f64> {
+        (self.slippage_fills > 0).then(|| self.total_slippage / self.slippage_fills as f64)
+    }
+
+    pub fn landing_rate(&self) -> Option<f64> {
+        (self.sent > 0).then(|| self.landed as f64 * 100.0 / self.sent as f64)
+    }
+}
+
+// Realized PnL and execution stats rebuilt from the journal, grouped by the
+// target that opened each position, by mint and by UTC day.
+#[derive(Debug, Clone, Default)]
+pub struct PnlReport {
+    pub total: PnlStats,
+    pub by_target: BTreeMap<String, PnlStats>,
+    pub by_mint: BTreeMap<String, PnlStats>,
+    pub by_day: BTreeMap<String, PnlStats>,
+    // Positions as of the last record
+    pub positions: BTreeMap<String, Position>,
+}
+
+impl PnlReport {
+    pub fn from_records(records: &[JournalRecord]) -> Self {
+        let mut report = Self::default();
+        // Target and open time of each mint's current position
+        let mut opened: HashMap<String, (String, DateTime<Utc>)> = HashMap::new();
+
+        for record in records {
+            // Late bundle outcomes of swaps already counted
+            if record.source == "bundle" {
+                continue;
+            }
+            let time = DateTime::parse_from_rfc3339(&record.timestamp)
+                .map(|time| time.with_timezone(&Utc))
+                .unwrap_or_default();
+            let target = record
+                .target_wallet
+                .clone()
+                .or_else(|| opened.get(&record.mint).map(|(target, _)| target.clone()))
+                .unwrap_or_else(|| "unknown".to_string());
+            let day = time.date_naive().to_string();
+            let keys = (target.as_str(), record.mint.as_str(), day.as_str());
+
+            // Only swaps that reached a send path carry a signature; failures
+            // while building or simulating never did
+            if record.decision != Decision::Skipped && record.signature.is_some() {
+                let landed = record.decision == Decision::Copied;
+                report.update(keys, |stats| {
+                    stats.sent += 1;
+                    if landed {
+                        stats.landed += 1;
+                    }
+                });
+            }
+
+            let Some(fill) = &record.fill else { continue };
+            let slippage = record
+                .trade
+                .as_ref()
+                .and_then(|trade| trade.price())
+                .zip(fill.curve_price())
+                .map(|(target_price, price)| {
+                    if fill.is_buy {
+                        (price / target_price - 1.0) * 100.0
+                    } else {
+                        (1.0 - price / target_price) * 100.0
+                    }
+                });
+
+            let position = report.positions.entry(record.mint.clone()).or_insert_with(|| Position {
+                mint: record.mint.clone(),
+                ..Position::default()
+            });
+            if position.token_amount == 0 && fill.is_buy {
+                opened.insert(record.mint.clone(), (target.clone(), time));
+            }
+            let realized_before = position.realized_pnl;
+            position.apply(fill);
+            let realized = position.realized_pnl - realized_before;
+            let closed = !fill.is_buy && position.token_amount == 0;
+            let position_pnl = position.realized_pnl;
+            let hold_secs = opened
+                .get(&record.mint)
+                .map(|(_, opened_at)| (time - *opened_at).num_seconds())
+                .unwrap_or_default();
+
+            report.update(keys, |stats| {
+                stats.fees += fill.costs();
+                stats.realized_pnl += realized;
+                if let Some(slippage) = slippage {
+                    stats.total_slippage += slippage;
+                    stats.slippage_fills += 1;
+                }
+                if closed {
+                    stats.closed += 1;
+                    stats.total_hold_secs += hold_secs;
+                    if position_pnl > 0 {
+                        stats.wins += 1;
+                    }
+                }
+            });
+
+            if closed {
+                // The next buy starts a fresh round trip
+                opened.remove(&record.mint);
+                report.positions.remove(&record.mint);
+            }
+        }
+
+        report
+    }
+
+    fn update(&mut self, (target, mint, day): (&str, &str, &str), apply: impl Fn(&mut PnlStats)) {
+        apply(&mut self.total);
+        apply(self.by_target.entry(target.to_string()).or_default());
+        apply(self.by_mint.entry(mint.to_string()).or_default());
+        apply(self.by_day.entry(day.to_string()).or_default());
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "group,key,realized_sol,closed,wins,win_rate_pct,avg_hold_secs,avg_slippage_pct,fees_sol,sent,landed,landing_rate_pct\n",
+        );
+        let groups = [
+            ("total", BTreeMap::from([("all".to_string(), self.total.clone())])),
+            ("target", self.by_target.clone()),
+            ("mint", self.by_mint.clone()),
+            ("day", self.by_day.clone()),
+        ];
+        for (group, rows) in groups {
+            for (key, stats) in rows {
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                    group,
+                    key,
+                    stats.realized_pnl as f64 / 1_000_000_000.0,
+                    stats.closed,
+                    stats.wins,
+                    format_optional(stats.win_rate()),
+                    format_optional(stats.average_hold_secs()),
+                    format_optional(stats.average_slippage()),
+                    stats.fees as f64 / 1_000_000_000.0,
+                    stats.sent,
+                    stats.landed,
+                    format_optional(stats.landing_rate()),
+                ));
+            }
+        }
+        csv
+    }
+}
+
+fn format_optional(value: Option<f64>) -> String {
+    value.map(|value| format!("{:.2}", value)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::engine::{journal::JournalTrade, positions::Fill},
+    };
+
+    const FEE: u64 = 5_000;
+
+    // `fill` is (is_buy, token amount, lamports)
+    fn record(
+        timestamp: &str,
+        mint: &str,
+        target: Option<&str>,
+        decision: Decision,
+        fill: Option<(bool, u64, u64)>,
+    ) -> JournalRecord {
+        let is_buy = fill.is_none_or(|(is_buy, _, _)| is_buy);
+        let mut record = JournalRecord::new("copy", mint, is_buy);
+        record.timestamp = timestamp.to_string();
+        record.target_wallet = target.map(str::to_string);
+        record.decision = decision;
+        if decision != Decision::Skipped {
+            record.signature = Some(format!("sig-{}", timestamp));
+        }
+        record.fill = fill.map(|(is_buy, token_amount, sol_amount)| Fill {
+            signature: String::new(),
+            mint: mint.to_string(),
+            is_buy,
+            slot: 0,
+            token_amount,
+            sol_amount,
+            excess_cost: 0,
+            fee: FEE,
+            curve_sol_amount: None,
+        });
+        record
+    }
+
+    fn records() -> Vec<JournalRecord> {
+        let mut first_buy = record(
+            "2024-01-01T23:58:00Z",
+            "mint1",
+            Some("alice"),
+            Decision::Copied,
+            Some((true, 1_000, 100_000)),
+        );
+        // 4,000 lamports of the cost went to fees rather than the curve
+        if let Some(fill) = first_buy.fill.as_mut() {
+            fill.curve_sol_amount = Some(96_000);
+        }
+        first_buy.trade = Some(JournalTrade {
+            mint: "mint1".to_string(),
+            user: "alice".to_string(),
+            sol_amount: 90_000,
+            token_amount: 1_000,
+            is_buy: true,
+            timestamp: 0,
+            virtual_sol_reserves: 0,
+            virtual_token_reserves: 0,
+        });
+
+        // Failed before anything was sent
+        let mut unsent = record("2024-01-01T23:58:40Z", "mint3", Some("bob"), Decision::Failed, None);
+        unsent.signature = None;
+
+        vec![
+            first_buy,
+            record("2024-01-01T23:58:10Z", "mint2", Some("bob"), Decision::Copied, Some((true, 1_000, 200_000))),
+            record("2024-01-01T23:58:20Z", "mint2", Some("bob"), Decision::NotLanded, None),
+            record("2024-01-01T23:58:30Z", "mint3", Some("bob"), Decision::Skipped, None),
+            unsent,
+            // The exit manager's sells carry no target; they inherit the opener
+            record("2024-01-01T23:59:00Z", "mint1", None, Decision::Copied, Some((false, 1_000, 150_000))),
+            record("2024-01-01T23:59:10Z", "mint2", None, Decision::Copied, Some((false, 500, 50_000))),
+            record("2024-01-02T00:00:10Z", "mint2", None, Decision::Copied, Some((false, 500, 40_000))),
+            record("2024-01-02T00:01:00Z", "mint4", Some("alice"), Decision::Copied, Some((true, 2_000, 100_000))),
+        ]
+    }
+
+    #[test]
+    fn totals_cover_wins_hold_time_and_landing_rate() {
+        let report = PnlReport::from_records(&records());
+        let total = &report.total;
+
+        assert_eq!(total.realized_pnl, 50_000 - 110_000);
+        assert_eq!((total.closed, total.wins), (2, 1));
+        assert_eq!(total.win_rate(), Some(50.0));
+        // 60s for mint1 and 120s for mint2
+        assert_eq!(total.average_hold_secs(), Some(90.0));
+        assert_eq!((total.sent, total.landed), (7, 6));
+        assert!((total.landing_rate().unwrap() - 600.0 / 7.0).abs() < 1e-9);
+        assert_eq!(total.fees, 5 * FEE + 4_000);
+        // Only the first buy has a target trade to compare with, and only on
+        // the curve price
+        assert_eq!(total.slippage_fills, 1);
+        assert!((total.average_slippage().unwrap() - (96.0 / 90.0 - 1.0) * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn groups_by_opening_target_mint_and_day() {
+        let report = PnlReport::from_records(&records());
+
+        let alice = &report.by_target["alice"];
+        assert_eq!((alice.realized_pnl, alice.closed, alice.wins, alice.sent), (50_000, 1, 1, 3));
+        let bob = &report.by_target["bob"];
+        assert_eq!((bob.realized_pnl, bob.closed, bob.wins), (-110_000, 1, 0));
+        assert_eq!((bob.sent, bob.landed), (4, 3));
+
+        assert_eq!(report.by_mint["mint2"].realized_pnl, -110_000);
+        assert!(!report.by_mint.contains_key("mint3"));
+
+        // Half of mint2's cost is released by each of its sells
+        assert_eq!(report.by_day["2024-01-01"].realized_pnl, 50_000 - 50_000);
+        assert_eq!(report.by_day["2024-01-02"].realized_pnl, -60_000);
+        assert_eq!(report.by_day["2024-01-02"].closed, 1);
+    }
+
+    #[test]
+    fn only_open_positions_remain() {
+        let report = PnlReport::from_records(&records());
+        assert_eq!(report.positions.keys().collect::<Vec<_>>(), vec!["mint4"]);
+        assert_eq!(report.positions["mint4"].token_amount, 2_000);
+        assert_eq!(report.positions["mint4"].cost_basis, 100_000);
+    }
+}
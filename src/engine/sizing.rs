@@ -0,0 +1,16 @@
+use crate::dex::pump_fun::PumpInfo;
+
+// Copy buys spend this share of the curve's virtual SOL reserves
+pub const COPY_BUY_PERCENT: f64 = 0.5;
+// Copy sells sell this share of what we hold
+pub const COPY_SELL_PERCENT: f64 = 0.5;
+
+// Lamports a copy buy of the mint spends, before the front-run guard.
+pub fn copy_buy_amount(pump_info: &PumpInfo) -> u64 {
+    (pump_info.virtual_sol_reserves as f64 * COPY_BUY_PERCENT) as u64
+}
+
+// Raw token units a copy sell sells out of `token_balance`.
+pub fn copy_sell_amount(token_balance: u64) -> u64 {
+    (token_balance as f64 * COPY_SELL_PERCENT) as u64
+}
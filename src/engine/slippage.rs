@@ -0,0 +1,116 @@
+use {
+    crate::{common::utils::import_optional_env_var, engine::executor::pump_error},
+    anyhow::{anyhow, Result},
+};
+
+const DEFAULT_SLIPPAGE_RETRY_STEP: u64 = 5; // percent
+const DEFAULT_MAX_PRICE_PREMIUM: u64 = 10; // percent
+
+// Retries a copy buy that hit the Pump slippage bound with wider slippage, as
+// long as the fresh quote stays close enough to what the target paid.
+#[derive(Debug, Clone)]
+pub struct SlippageRetryPolicy {
+    pub step: u64,
+    pub max_slippage: u64,
+    pub max_price_premium: u64,
+}
+
+impl SlippageRetryPolicy {
+    // Enabled by `SLIPPAGE_RETRY_MAX`; `None` means a slippage failure is final.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(max_slippage) = import_optional_env_var("SLIPPAGE_RETRY_MAX") else {
+            return Ok(None);
+        };
+        let max_slippage = max_slippage
+            .parse::<u64>()
+            .map_err(|e| anyhow!("Invalid SLIPPAGE_RETRY_MAX: {}", e))?;
+        let step = import_optional_env_var("SLIPPAGE_RETRY_STEP")
+            .map(|v| v.parse::<u64>().map_err(|e| anyhow!("Invalid SLIPPAGE_RETRY_STEP: {}", e)))
+            .transpose()?
+            .unwrap_or(DEFAULT_SLIPPAGE_RETRY_STEP);
+        if step == 0 {
+            return Err(anyhow!("Invalid SLIPPAGE_RETRY_STEP: must be above 0"));
+        }
+        let max_price_premium = import_optional_env_var("MAX_PRICE_PREMIUM")
+            .map(|v| v.parse::<u64>().map_err(|e| anyhow!("Invalid MAX_PRICE_PREMIUM: {}", e)))
+            .transpose()?
+            .unwrap_or(DEFAULT_MAX_PRICE_PREMIUM);
+
+        Ok(Some(Self {
+            step,
+            max_slippage,
+            max_price_premium,
+        }))
+    }
+
+    // Only a buy that hit the slippage bound is worth retrying.
+    pub fn is_retryable(&self, is_buy: bool, error: &anyhow::Error) -> bool {
+        is_buy && pump_error(error).is_some_and(|error| error.is_slippage())
+    }
+
+    // Slippage for the next attempt, or `None` once the cap is reached.
+    pub fn next_slippage(&self, slippage: u64) -> Option<u64> {
+        if slippage >= self.max_slippage {
+            return None;
+        }
+        Some((slippage + self.step).min(self.max_slippage))
+    }
+
+    // Both prices in lamports per raw token unit.
+    pub fn within_premium(&self, quote_price: f64, target_price: f64) -> bool {
+        quote_price <= target_price * (100 + self.max_price_premium) as f64 / 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::dex::{pump_error::PumpError, pump_fun::SimulationFailure},
+    };
+
+    fn policy() -> SlippageRetryPolicy {
+        SlippageRetryPolicy {
+            step: 5,
+            max_slippage: 17,
+            max_price_premium: 10,
+        }
+    }
+
+    #[test]
+    fn steps_up_to_the_cap_then_stops() {
+        let policy = policy();
+        let mut attempts = vec![5];
+        while let Some(next) = policy.next_slippage(*attempts.last().unwrap()) {
+            attempts.push(next);
+        }
+
+        assert_eq!(attempts, vec![5, 10, 15, 17]);
+        assert_eq!(policy.next_slippage(20), None);
+    }
+
+    #[test]
+    fn retries_only_buys_that_hit_the_slippage_bound() {
+        let policy = policy();
+        let simulated = anyhow::Error::new(SimulationFailure {
+            error: "custom program error: 0x1772".to_string(),
+            pump_error: Some(PumpError::TooMuchSolRequired),
+            units_consumed: None,
+            logs: Vec::new(),
+        });
+
+        assert!(policy.is_retryable(true, &simulated));
+        assert!(policy.is_retryable(true, &PumpError::TooMuchSolRequired.into()));
+        assert!(!policy.is_retryable(false, &PumpError::TooLittleSolReceived.into()));
+        assert!(!policy.is_retryable(true, &PumpError::BondingCurveComplete.into()));
+        assert!(!policy.is_retryable(true, &anyhow!("connection reset")));
+    }
+
+    #[test]
+    fn premium_is_measured_against_the_target_fill() {
+        let policy = policy();
+
+        assert!(policy.within_premium(110.0, 100.0));
+        assert!(!policy.within_premium(110.5, 100.0));
+    }
+}
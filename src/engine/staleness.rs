@@ -0,0 +1,125 @@
+use {
+    crate::common::utils::import_optional_env_var,
+    anyhow::{anyhow, Result},
+    chrono::Utc,
+    std::fmt,
+};
+
+// Block times are whole seconds, so a block may have landed up to this much
+// later than its block time says.
+const BLOCK_TIME_SLACK_MS: i64 = 999; // milliseconds
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleSignal {
+    Slots { age: u64, max: u64 },
+    Millis { age: i64, max: u64 },
+}
+
+// Drops target trades that are too old to be worth copying by the time we'd
+// send, measured from the target's slot and block time.
+#[derive(Debug, Clone)]
+pub struct StalenessFilter {
+    pub max_slots: Option<u64>,
+    pub max_age_ms: Option<u64>,
+}
+
+impl StalenessFilter {
+    // From `MAX_SIGNAL_AGE_SLOTS` and `MAX_SIGNAL_AGE_MS`; `None` when neither is set.
+    pub fn from_env() -> Result<Option<Self>> {
+        let max_slots = import_optional_env_var("MAX_SIGNAL_AGE_SLOTS")
+            .map(|v| v.parse::<u64>().map_err(|e| anyhow!("Invalid MAX_SIGNAL_AGE_SLOTS: {}", e)))
+            .transpose()?;
+        let max_age_ms = import_optional_env_var("MAX_SIGNAL_AGE_MS")
+            .map(|v| v.parse::<u64>().map_err(|e| anyhow!("Invalid MAX_SIGNAL_AGE_MS: {}", e)))
+            .transpose()?;
+        if max_slots.is_none() && max_age_ms.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self { max_slots, max_age_ms }))
+    }
+
+    pub fn check(&self, target_slot: u64, block_time: Option<i64>, current_slot: Option<u64>) -> Option<StaleSignal> {
+        self.check_at(target_slot, block_time, current_slot, Utc::now().timestamp_millis())
+    }
+
+    // `block_time` is the target's block time in seconds; the age is measured
+    // from the latest instant in that second so a fresh trade is never dropped
+    // for rounding. Unknown inputs pass their check. `now_ms` is the wall clock,
+    // or the replayed one in a backtest.
+    pub fn check_at(
+        &self,
+        target_slot: u64,
+        block_time: Option<i64>,
+        current_slot: Option<u64>,
+        now_ms: i64,
+    ) -> Option<StaleSignal> {
+        if let (Some(max), Some(current_slot)) = (self.max_slots, current_slot) {
+            let age = current_slot.saturating_sub(target_slot);
+            if age > max {
+                return Some(StaleSignal::Slots { age, max });
+            }
+        }
+        if let (Some(max), Some(block_time)) = (self.max_age_ms, block_time) {
+            let age = (now_ms - block_time * 1000 - BLOCK_TIME_SLACK_MS).max(0);
+            if age > max as i64 {
+                return Some(StaleSignal::Millis { age, max });
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for StaleSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StaleSignal::Slots { age, max } => write!(f, "{} slots old (max {})", age, max),
+            StaleSignal::Millis { age, max } => write!(f, "{}ms old (max {}ms)", age, max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(max_slots: Option<u64>, max_age_ms: Option<u64>) -> StalenessFilter {
+        StalenessFilter { max_slots, max_age_ms }
+    }
+
+    #[test]
+    fn drops_trades_too_many_slots_behind() {
+        let filter = filter(Some(3), None);
+
+        assert_eq!(filter.check_at(100, None, Some(103), 0), None);
+        assert_eq!(filter.check_at(100, None, Some(104), 0), Some(StaleSignal::Slots { age: 4, max: 3 }));
+    }
+
+    #[test]
+    fn measures_age_from_the_end_of_the_block_time_second() {
+        let filter = filter(None, Some(500));
+        let block_time = 1_700_000_000;
+
+        // Landed anywhere in that second, so 1.4s after its start may be only 0.4s old.
+        assert_eq!(filter.check_at(100, Some(block_time), None, block_time * 1000 + 1_400), None);
+        assert_eq!(
+            filter.check_at(100, Some(block_time), None, block_time * 1000 + 1_600),
+            Some(StaleSignal::Millis { age: 601, max: 500 })
+        );
+    }
+
+    #[test]
+    fn a_missing_block_time_passes_the_age_check() {
+        let filter = filter(None, Some(500));
+
+        assert_eq!(filter.check_at(100, None, Some(1_000), i64::MAX), None);
+    }
+
+    #[test]
+    fn a_missing_current_slot_passes_the_slot_check() {
+        let filter = filter(Some(3), Some(500));
+        let block_time = 1_700_000_000;
+
+        assert_eq!(filter.check_at(100, Some(block_time), None, block_time * 1000), None);
+    }
+}
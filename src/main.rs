@@ -2,20 +2,40 @@ use {
     dotenvy::dotenv,
     crate::{
         common::{
+            blockhash::BlockhashCache,
             logger::Logger,
-            utils::{create_nonblocking_rpc_client, create_rpc_client, import_env_var, import_wallet, AppState},
+            lookup_table::{get_lookup_table, import_lookup_table},
+            nonce::{import_nonce_account, DurableNonce},
+            rpc_pool::RpcPool,
+            utils::{import_env_var, import_optional_env_var, import_wallet, AppState},
         },
-        engine::monitor::wallet_monitor::monitor_wallet,
+        engine::{
+            dispatcher::MintDispatcher,
+            exit::{ExitManager, ExitRules},
+            filters::EntryFilters,
+            front_run::FrontRunGuard,
+            journal::Journal,
+            monitor::{
+                recorder::TransactionRecorder,
+                wallet_monitor::{monitor_wallet, CopyContext},
+            },
+            paper::PaperPortfolio,
+            positions::PositionBook,
+            reconcile::reconcile_positions,
+            slippage::SlippageRetryPolicy,
+            staleness::StalenessFilter,
+        },
+        services::jito::{init_tip_accounts, run_tip_stream, TipConfig},
     },
     anyhow::Result,
     solana_sdk::signature::Signer,
     std::sync::Arc,
 };
 
-mod common;
-mod dex;
+mod commands;
 mod engine;
-mod services;
+
+use pumpfun_copy::{common, dex, services};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -26,28 +46,128 @@ async fn main() -> Result<()> {
     let logger = Logger::new("[MAIN]".to_string());
     logger.info("Starting PumpFun sniper bot...".to_string());
 
+    // Offline subcommands (e.g. `pnl`) need no RPC
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = commands::run_offline(&args).await {
+        return result;
+    }
+
     // Initialize clients and state
-    let rpc_client = Arc::new(create_rpc_client()?);
-    let rpc_nonblocking_client = Arc::new(create_nonblocking_rpc_client().await?);
+    // Reads and sends fail over across the configured RPC endpoints
+    let rpc_pool = RpcPool::from_env()?;
+    let rpc_nonblocking_client = Arc::new(rpc_pool.client());
     let wallet = import_wallet()?;
     
     logger.info(format!("Bot wallet: {}", wallet.pubkey()));
 
+    // Keep a fresh blockhash ready for signing swaps
+    let blockhash_cache = BlockhashCache::new();
+    tokio::spawn(blockhash_cache.clone().run(rpc_nonblocking_client.clone()));
+
+    // Build v0 swaps against the bot's lookup table when one is configured
+    let lookup_table = match import_lookup_table()? {
+        Some(lookup_table) => {
+            let table = get_lookup_table(&rpc_nonblocking_client, &lookup_table).await?;
+            logger.info(format!("Lookup table: {} ({} addresses)", table.key, table.addresses.len()));
+            Some(table)
+        }
+        None => None,
+    };
+
+    let nonce_account = import_nonce_account()?;
+    if let Some(nonce_account) = &nonce_account {
+        logger.info(format!("Durable nonce account: {}", nonce_account));
+    }
+
     let state = AppState {
-        rpc_client: rpc_client.clone(),
         rpc_nonblocking_client: rpc_nonblocking_client.clone(),
+        rpc_pool: rpc_pool.clone(),
         wallet: wallet.clone(),
+        blockhash_cache,
+        nonce: nonce_account.map(DurableNonce::new),
+        lookup_table,
+        tip_config: TipConfig::from_env()?,
     };
 
+    // Subcommands (e.g. `nonce create`) run once and exit
+    if let Some(result) = commands::run(&args, &state).await {
+        return result;
+    }
+
+    logger.info(format!("RPC endpoints: {}", rpc_pool.endpoint_count()));
+    tokio::spawn(rpc_pool.clone().run_health_checks());
+
     // Get configuration from environment
     let slippage = import_env_var("SLIPPAGE").parse::<u64>().unwrap_or(5);
     let ws_url = import_env_var("RPC_WSS");
+
+    // Keep Jito tips in line with the landed tip floor
+    if let Some(tip_stream_url) = import_optional_env_var("JITO_TIP_STREAM_URL") {
+        tokio::spawn(run_tip_stream(tip_stream_url));
+    }
+
+    // Sell held mints on take-profit / stop-loss triggers
+    let positions = PositionBook::new();
+    tokio::spawn(positions.clone().run_marks(rpc_nonblocking_client.clone()));
+    let journal = Journal::from_env()?;
+    logger.info(format!("Trade journal: {}", journal.path().display()));
+    let paper = PaperPortfolio::from_env(journal.path())?;
+    if let Some(paper) = &paper {
+        logger.warning("Paper trading: swaps are simulated against live reserves, nothing is sent".to_string());
+        paper.log_summary();
+    } else {
+        // Every swap is also sent as a tipped Jito bundle
+        init_tip_accounts().await?;
+    }
+    // Copies and exits on the same mint run one at a time, in order
+    let dispatcher = MintDispatcher::new();
+    let exit_manager = ExitManager::new(
+        ExitRules::from_env()?,
+        positions.clone(),
+        journal.clone(),
+        dispatcher.clone(),
+        paper.clone(),
+    );
+
+    // Pick up holdings left over from earlier runs or traded by hand
+    if let Err(e) = reconcile_positions(
+        &state.rpc_nonblocking_client,
+        &state.wallet.pubkey(),
+        journal.path(),
+        &positions,
+        &exit_manager,
+        paper.as_ref(),
+    )
+    .await
+    {
+        logger.error(format!("Position reconciliation failed: {}", e));
+    }
+    if exit_manager.is_enabled() {
+        tokio::spawn(exit_manager.clone().run(state.clone(), slippage, true));
+    }
     
     logger.success("Bot initialization complete".to_string());
     logger.info("Starting wallet monitor...".to_string());
 
+    let recorder = TransactionRecorder::from_env()?;
+    if let Some(recorder) = &recorder {
+        logger.info(format!("Recording target transactions to {}", recorder.dir().display()));
+    }
+
     // Start monitoring
-    monitor_wallet(&ws_url, state, slippage, true).await?;
+    let context = CopyContext {
+        exit_manager,
+        filters: EntryFilters::from_env()?,
+        positions,
+        journal,
+        recorder,
+        dispatcher,
+        staleness: StalenessFilter::from_env()?,
+        front_run: FrontRunGuard::from_env()?,
+        retry_policy: SlippageRetryPolicy::from_env()?,
+        paper,
+    };
+    monitor_wallet(&ws_url, state, context, slippage, true).await?;
 
     Ok(())
 }
@@ -1,24 +1,238 @@
 use anyhow::{Result, anyhow};
-use solana_sdk::pubkey::Pubkey;
-use std::str::FromStr;
+use futures::StreamExt;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::RpcRequest};
+use solana_sdk::{pubkey::Pubkey, transaction::VersionedTransaction};
+use std::{str::FromStr, sync::{Arc, LazyLock, RwLock}, time::{Duration, Instant}};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
 use bs58;
 
+use crate::common::{logger::Logger, utils::{import_env_var, import_optional_env_var, post_json_rpc}};
+
+const BUNDLE_STATUS_TIMEOUT: u64 = 30; // seconds
+const BUNDLE_STATUS_INTERVAL: u64 = 500; // milliseconds
+const DEFAULT_TIP_VALUE: f64 = 0.004; // SOL
+const DEFAULT_TIP_MAX: f64 = 0.01; // SOL
+const DEFAULT_TIP_PERCENTILE: &str = "50";
+const TIP_PERCENTILES: [&str; 7] = ["25", "50", "75", "95", "99", "ema", "ema50"];
+const TIP_FLOOR_MAX_AGE: u64 = 60; // seconds
+const TIP_STREAM_RECONNECT_DELAY: u64 = 5; // seconds
+
+// Outcome of `simulateBundle`; `error` is set when any transaction failed
+#[derive(Debug, Clone)]
+pub struct BundleSimulation {
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+    pub units_consumed: u64,
+}
+
+// Tip floor fed by `run_tip_stream` and read by `get_tip_value`
+static TIP_FLOOR: LazyLock<TipFloorCache> = LazyLock::new(TipFloorCache::default);
+
+// Loaded once by `init_tip_accounts`
+static TIP_ACCOUNTS: RwLock<Vec<Pubkey>> = RwLock::new(Vec::new());
+
+// Landed tip percentiles (in SOL) as published by the Jito tip floor feed
+#[derive(Debug, Clone, Deserialize)]
+pub struct TipFloor {
+    pub time: String,
+    pub landed_tips_25th_percentile: f64,
+    pub landed_tips_50th_percentile: f64,
+    pub landed_tips_75th_percentile: f64,
+    pub landed_tips_95th_percentile: f64,
+    pub landed_tips_99th_percentile: f64,
+    pub ema_landed_tips_50th_percentile: f64,
+}
+
+impl TipFloor {
+    pub fn percentile(&self, percentile: &str) -> Option<f64> {
+        match percentile {
+            "25" => Some(self.landed_tips_25th_percentile),
+            "50" => Some(self.landed_tips_50th_percentile),
+            "75" => Some(self.landed_tips_75th_percentile),
+            "95" => Some(self.landed_tips_95th_percentile),
+            "99" => Some(self.landed_tips_99th_percentile),
+            "ema" | "ema50" => Some(self.ema_landed_tips_50th_percentile),
+            _ => None,
+        }
+    }
+}
+
+// How a tip is picked from the tip floor
+#[derive(Debug, Clone)]
+pub struct TipConfig {
+    pub fallback: f64,
+    pub max_tip: f64,
+    pub percentile: String,
+    pub max_age: Duration,
+}
+
+impl TipConfig {
+    // Read once at startup, so a bad value fails the bot rather than every swap.
+    pub fn from_env() -> Result<Self> {
+        let fallback = import_optional_env_var("JITO_TIP_VALUE")
+            .map(|v| v.parse::<f64>().map_err(|e| anyhow!("Invalid JITO_TIP_VALUE: {}", e)))
+            .transpose()?
+            .unwrap_or(DEFAULT_TIP_VALUE);
+        let max_tip = import_optional_env_var("JITO_TIP_MAX")
+            .map(|v| v.parse::<f64>().map_err(|e| anyhow!("Invalid JITO_TIP_MAX: {}", e)))
+            .transpose()?
+            .unwrap_or(DEFAULT_TIP_MAX);
+        let percentile = import_optional_env_var("JITO_TIP_PERCENTILE")
+            .unwrap_or_else(|| DEFAULT_TIP_PERCENTILE.to_string());
+        if !TIP_PERCENTILES.contains(&percentile.as_str()) {
+            return Err(anyhow!(
+                "Invalid JITO_TIP_PERCENTILE: {} (expected one of {})",
+                percentile,
+                TIP_PERCENTILES.join(", ")
+            ));
+        }
+        if fallback < 0.0 || max_tip < 0.0 {
+            return Err(anyhow!("JITO_TIP_VALUE and JITO_TIP_MAX must not be negative"));
+        }
+
+        Ok(Self {
+            fallback,
+            max_tip,
+            percentile,
+            max_age: Duration::from_secs(TIP_FLOOR_MAX_AGE),
+        })
+    }
+}
+
+// Latest tip floor from the stream and when it was received
+#[derive(Debug, Clone, Default)]
+pub struct TipFloorCache {
+    latest: Arc<RwLock<Option<(TipFloor, Instant)>>>,
+}
+
+impl TipFloorCache {
+    pub fn update(&self, floor: TipFloor) {
+        if let Ok(mut latest) = self.latest.write() {
+            *latest = Some((floor, Instant::now()));
+        }
+    }
+
+    // The configured percentile of the latest tip floor, the fallback when
+    // there is none or it has gone stale, and never more than the max tip.
+    pub fn tip(&self, config: &TipConfig) -> Result<f64> {
+        let tip = match self.latest.read().map_err(|_| anyhow!("Tip floor lock poisoned"))?.as_ref() {
+            Some((floor, received)) if received.elapsed() < config.max_age => floor
+                .percentile(&config.percentile)
+                .ok_or_else(|| anyhow!("Unknown JITO_TIP_PERCENTILE: {}", config.percentile))?,
+            _ => config.fallback,
+        };
+        Ok(tip.min(config.max_tip))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleOutcome {
+    // The slot is unknown when neither status endpoint reported it
+    Landed { slot: Option<u64> },
+    Failed,
+    Invalid,
+    PendingTimeout,
+}
+
+// Fetches the block engine's tip accounts with `getTipAccounts`. Bundles
+// can't land without a tip to one of them, so an empty list is an error.
 pub async fn init_tip_accounts() -> Result<()> {
+    let accounts = parse_tip_accounts(&bundle_rpc("getTipAccounts", json!([])).await?)?;
+    let logger = Logger::new("[JITO]".to_string());
+    logger.info(format!("Loaded {} tip accounts", accounts.len()));
+
+    *TIP_ACCOUNTS.write().map_err(|_| anyhow!("Tip accounts lock poisoned"))? = accounts;
     Ok(())
 }
 
-pub async fn get_tip_account() -> Result<Pubkey> {
-    let pubkey_str = "11111111111111111111111111111111";
-    if let Err(_) = bs58::decode(pubkey_str).into_vec() {
-        return Err(anyhow!("Invalid Base58 string for tip account"));
+pub fn parse_tip_accounts(result: &Value) -> Result<Vec<Pubkey>> {
+    let accounts = result
+        .as_array()
+        .ok_or_else(|| anyhow!("getTipAccounts returned no list: {}", result))?
+        .iter()
+        .map(|account| {
+            let account = account.as_str().ok_or_else(|| anyhow!("Invalid tip account: {}", account))?;
+            Pubkey::from_str(account).map_err(|e| anyhow!("Invalid tip account {}: {}", account, e))
+        })
+        .collect::<Result<Vec<Pubkey>>>()?;
+    if accounts.is_empty() {
+        return Err(anyhow!("getTipAccounts returned no tip accounts"));
     }
-    
-    Ok(Pubkey::from_str(pubkey_str)
-        .map_err(|e| anyhow!("Failed to create tip account pubkey: {}", e))?)
+    Ok(accounts)
+}
+
+// A random tip account per bundle, spreading tips across them as Jito advises.
+pub fn get_tip_account() -> Result<Pubkey> {
+    let accounts = TIP_ACCOUNTS.read().map_err(|_| anyhow!("Tip accounts lock poisoned"))?;
+    accounts
+        .choose(&mut rand::thread_rng())
+        .copied()
+        .ok_or_else(|| anyhow!("Tip accounts not loaded"))
 }
 
-pub async fn get_tip_value() -> Result<f64> {
-    Ok(0.004)
+// Returns the configured percentile of the latest tip floor, falling back to
+// `JITO_TIP_VALUE` when the stream is not running or has gone stale, and never
+// more than `JITO_TIP_MAX`.
+pub fn get_tip_value(config: &TipConfig) -> Result<f64> {
+    TIP_FLOOR.tip(config)
+}
+
+// The feed sends a JSON array holding the latest tip floor on each update.
+pub fn parse_tip_floor(message: &str) -> Result<TipFloor> {
+    let floors: Vec<TipFloor> = serde_json::from_str(message)?;
+    floors
+        .into_iter()
+        .last()
+        .ok_or_else(|| anyhow!("Empty tip floor message"))
+}
+
+// Consumes the tip floor WebSocket feed forever, reconnecting on errors.
+pub async fn run_tip_stream(url: String) {
+    stream_tip_floor(url, TIP_FLOOR.clone()).await
+}
+
+// `run_tip_stream` into any cache.
+pub async fn stream_tip_floor(url: String, cache: TipFloorCache) {
+    let logger = Logger::new("[JITO-TIP-STREAM]".to_string());
+
+    loop {
+        match connect_async(url.as_str()).await {
+            Ok((mut stream, _)) => {
+                logger.info(format!("Connected to tip floor stream: {}", url));
+                while let Some(message) = stream.next().await {
+                    match message {
+                        Ok(Message::Text(text)) => match parse_tip_floor(&text) {
+                            Ok(floor) => {
+                                logger.debug(format!(
+                                    "[TIP FLOOR] => 25th: {}, 50th: {}, 75th: {}, 95th: {}, 99th: {} SOL",
+                                    floor.landed_tips_25th_percentile,
+                                    floor.landed_tips_50th_percentile,
+                                    floor.landed_tips_75th_percentile,
+                                    floor.landed_tips_95th_percentile,
+                                    floor.landed_tips_99th_percentile,
+                                ));
+                                cache.update(floor);
+                            }
+                            Err(e) => logger.warning(format!("Failed to parse tip floor message: {}", e)),
+                        },
+                        Ok(Message::Close(_)) => break,
+                        Ok(_) => {}
+                        Err(e) => {
+                            logger.error(format!("Tip floor stream error: {}", e));
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => logger.error(format!("Failed to connect to tip floor stream {}: {}", url, e)),
+        }
+
+        logger.warning(format!("Reconnecting to tip floor stream in {}s", TIP_STREAM_RECONNECT_DELAY));
+        tokio::time::sleep(Duration::from_secs(TIP_STREAM_RECONNECT_DELAY)).await;
+    }
 }
 
 pub fn validate_mint_address(mint_address: &str) -> Result<Pubkey> {
@@ -28,4 +242,229 @@ pub fn validate_mint_address(mint_address: &str) -> Result<Pubkey> {
     
     Pubkey::from_str(mint_address)
         .map_err(|e| anyhow!("Failed to create mint address pubkey: {}", e))
-} 
\ No newline at end of file
+} 
+
+pub async fn send_bundle(transactions: &[VersionedTransaction]) -> Result<String> {
+    let encoded = transactions
+        .iter()
+        .map(|tx| Ok(bs58::encode(bincode::serialize(tx)?).into_string()))
+        .collect::<Result<Vec<String>>>()?;
+
+    let result = bundle_rpc("sendBundle", json!([encoded])).await?;
+    result
+        .as_str()
+        .map(|id| id.to_string())
+        .ok_or_else(|| anyhow!("sendBundle returned no bundle id: {}", result))
+}
+
+// Polls the block engine until the bundle reaches a terminal state or the
+// timeout passes. `getInflightBundleStatuses` only covers the last five
+// minutes, so `getBundleStatuses` is used to confirm the landing slot.
+pub async fn wait_for_bundle(bundle_id: &str) -> Result<BundleOutcome> {
+    let logger = Logger::new("[JITO-BUNDLE]".to_string());
+    let start_time = Instant::now();
+    let mut last_status = String::new();
+
+    while start_time.elapsed() < Duration::from_secs(BUNDLE_STATUS_TIMEOUT) {
+        match get_inflight_bundle_status(bundle_id).await {
+            Ok((status, landed_slot)) => {
+                if status != last_status {
+                    logger.debug(format!("Bundle {} => {} ({:?})", bundle_id, status, start_time.elapsed()));
+                }
+                match status.as_str() {
+                    "Landed" => {
+                        let slot = match landed_slot {
+                            Some(slot) => Some(slot),
+                            None => get_bundle_landed_slot(bundle_id).await.unwrap_or_else(|e| {
+                                logger.warning(format!("Failed to get landed slot of bundle {}: {}", bundle_id, e));
+                                None
+                            }),
+                        };
+                        return Ok(BundleOutcome::Landed { slot });
+                    }
+                    "Failed" => return Ok(BundleOutcome::Failed),
+                    "Invalid" => {
+                        // Not yet (or no longer) known to the in-flight index
+                        match get_bundle_landed_slot(bundle_id).await {
+                            Ok(Some(slot)) => return Ok(BundleOutcome::Landed { slot: Some(slot) }),
+                            Ok(None) => {}
+                            Err(e) => logger.warning(format!("Failed to get status of bundle {}: {}", bundle_id, e)),
+                        }
+                    }
+                    _ => {}
+                }
+                last_status = status;
+            }
+            Err(e) => logger.warning(format!("Failed to get status of bundle {}: {}", bundle_id, e)),
+        }
+        tokio::time::sleep(Duration::from_millis(BUNDLE_STATUS_INTERVAL)).await;
+    }
+
+    if last_status == "Invalid" {
+        Ok(BundleOutcome::Invalid)
+    } else {
+        Ok(BundleOutcome::PendingTimeout)
+    }
+}
+
+pub async fn get_inflight_bundle_status(bundle_id: &str) -> Result<(String, Option<u64>)> {
+    let result = bundle_rpc("getInflightBundleStatuses", json!([[bundle_id]])).await?;
+    let entry = result["value"]
+        .get(0)
+        .ok_or_else(|| anyhow!("No in-flight status for bundle {}", bundle_id))?;
+
+    let status = entry["status"].as_str().unwrap_or("Invalid").to_string();
+    Ok((status, entry["landed_slot"].as_u64()))
+}
+
+pub async fn get_bundle_landed_slot(bundle_id: &str) -> Result<Option<u64>> {
+    let result = bundle_rpc("getBundleStatuses", json!([[bundle_id]])).await?;
+    let entry = match result["value"].get(0) {
+        Some(entry) if !entry.is_null() => entry,
+        _ => return Ok(None),
+    };
+
+    if entry["err"].is_null() || entry["err"].get("Ok").is_some() {
+        Ok(entry["slot"].as_u64())
+    } else {
+        Ok(None)
+    }
+}
+
+// Runs the bundle against a Jito-Solana RPC node without sending it. The
+// method is only served by Jito-Solana validators, not the block engine.
+pub async fn simulate_bundle(client: &RpcClient, transactions: &[VersionedTransaction]) -> Result<BundleSimulation> {
+    let encoded = transactions
+        .iter()
+        .map(|tx| Ok(base64::Engine::encode(&base64::engine::general_purpose::STANDARD, bincode::serialize(tx)?)))
+        .collect::<Result<Vec<String>>>()?;
+    let account_configs = vec![Value::Null; transactions.len()];
+
+    let result: Value = client
+        .send(
+            RpcRequest::Custom { method: "simulateBundle" },
+            json!([
+                { "encodedTransactions": encoded },
+                {
+                    "preExecutionAccountsConfigs": account_configs,
+                    "postExecutionAccountsConfigs": account_configs,
+                    "skipSigVerify": false,
+                    "replaceRecentBlockhash": false,
+                }
+            ]),
+        )
+        .await
+        .map_err(|e| anyhow!("simulateBundle failed: {}", e))?;
+
+    let value = &result["value"];
+    let error = match &value["summary"] {
+        Value::String(summary) if summary == "succeeded" => None,
+        summary => Some(summary["failed"]["error"].to_string()),
+    };
+
+    let mut logs = Vec::new();
+    let mut units_consumed = 0;
+    if let Some(results) = value["transactionResults"].as_array() {
+        for tx_result in results {
+            if let Some(tx_logs) = tx_result["logs"].as_array() {
+                logs.extend(tx_logs.iter().filter_map(|log| log.as_str().map(|log| log.to_string())));
+            }
+            units_consumed += tx_result["unitsConsumed"].as_u64().unwrap_or_default();
+        }
+    }
+
+    Ok(BundleSimulation { error, logs, units_consumed })
+}
+
+async fn bundle_rpc(method: &str, params: Value) -> Result<Value> {
+    let url = format!("{}/api/v1/bundles", import_env_var("JITO_BLOCK_ENGINE_URL").trim_end_matches('/'));
+    post_json_rpc(&url, method, params).await
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        futures::SinkExt,
+        tokio::net::TcpListener,
+        tokio_tungstenite::accept_async,
+    };
+
+    const TIP_FLOOR_MESSAGE: &str = r#"[{
+        "time": "2024-09-01T12:58:00Z",
+        "landed_tips_25th_percentile": 0.0011,
+        "landed_tips_50th_percentile": 0.0022,
+        "landed_tips_75th_percentile": 0.0033,
+        "landed_tips_95th_percentile": 0.0044,
+        "landed_tips_99th_percentile": 0.0055,
+        "ema_landed_tips_50th_percentile": 0.0021
+    }]"#;
+
+    fn config(percentile: &str, max_tip: f64, max_age: Duration) -> TipConfig {
+        TipConfig {
+            fallback: 0.004,
+            max_tip,
+            percentile: percentile.to_string(),
+            max_age,
+        }
+    }
+
+    // Serves one tip floor message to the first client and keeps it open.
+    async fn serve_tip_floor() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut stream = accept_async(socket).await.unwrap();
+            stream.send(Message::Text(TIP_FLOOR_MESSAGE.to_string())).await.unwrap();
+            while stream.next().await.is_some() {}
+        });
+        url
+    }
+
+    #[test]
+    fn parse_tip_accounts_rejects_an_empty_list() {
+        let accounts = parse_tip_accounts(&json!([
+            "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+            "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe"
+        ]))
+        .unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert!(parse_tip_accounts(&json!([])).is_err());
+        assert!(parse_tip_accounts(&json!(["not a pubkey"])).is_err());
+        assert!(parse_tip_accounts(&Value::Null).is_err());
+    }
+
+    #[tokio::test]
+    async fn tip_stream_feeds_percentile_clamp_and_fallback() {
+        let cache = TipFloorCache::default();
+        let fresh = Duration::from_secs(60);
+        // Nothing received yet
+        assert_eq!(cache.tip(&config("75", 0.01, fresh)).unwrap(), 0.004);
+
+        let url = serve_tip_floor().await;
+        tokio::spawn(stream_tip_floor(url, cache.clone()));
+        let received = tokio::time::timeout(Duration::from_secs(5), async {
+            while cache.latest.read().unwrap().is_none() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(received.is_ok(), "tip floor never arrived");
+
+        assert_eq!(cache.tip(&config("75", 0.01, fresh)).unwrap(), 0.0033);
+        assert_eq!(cache.tip(&config("ema", 0.01, fresh)).unwrap(), 0.0021);
+        assert_eq!(cache.tip(&config("99", 0.005, fresh)).unwrap(), 0.005);
+        assert!(cache.tip(&config("60", 0.01, fresh)).is_err());
+        // Stale floors fall back to JITO_TIP_VALUE, still clamped
+        assert_eq!(cache.tip(&config("75", 0.01, Duration::ZERO)).unwrap(), 0.004);
+        assert_eq!(cache.tip(&config("75", 0.002, Duration::ZERO)).unwrap(), 0.002);
+    }
+
+    #[test]
+    fn parse_tip_floor_takes_last_entry() {
+        let floor = parse_tip_floor(TIP_FLOOR_MESSAGE).unwrap();
+        assert_eq!(floor.percentile("50"), Some(0.0022));
+        assert!(parse_tip_floor("[]").is_err());
+    }
+}
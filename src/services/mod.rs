@@ -1 +1,2 @@
 pub mod jito;
+pub mod relayer;
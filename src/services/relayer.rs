@@ -0,0 +1,26 @@
+use anyhow::{Result, anyhow};
+use serde_json::json;
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::common::utils::post_json_rpc;
+
+// Submits a signed transaction to a third-party relayer exposing the standard
+// `sendTransaction` JSON-RPC method.
+pub async fn send_transaction(relayer_url: &str, transaction: &VersionedTransaction) -> Result<String> {
+    let encoded = base64::Engine::encode(
+        &base64::engine::general_purpose::STANDARD,
+        bincode::serialize(transaction)?,
+    );
+
+    let result = post_json_rpc(
+        relayer_url,
+        "sendTransaction",
+        json!([encoded, { "encoding": "base64", "skipPreflight": true }]),
+    )
+    .await?;
+
+    result
+        .as_str()
+        .map(|signature| signature.to_string())
+        .ok_or_else(|| anyhow!("Relayer {} returned no signature: {}", relayer_url, result))
+}